@@ -0,0 +1,105 @@
+use bevy::audio::AudioSink;
+use bevy::prelude::*;
+
+use crate::player::Accumulator;
+use crate::ui::GameState;
+
+// 音效，由各系统发出事件，在此统一播放
+pub struct AudioPlugin;
+
+impl Plugin for AudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GameSound>()
+            .add_systems(Startup, setup_game_sounds)
+            .add_systems(Update, (play_game_sounds, sync_accumulation_sound))
+            .add_systems(OnEnter(GameState::Paused), pause_accumulation_sound)
+            .add_systems(OnExit(GameState::Paused), resume_accumulation_sound);
+    }
+}
+
+#[derive(Debug, Resource)]
+pub struct GameSounds {
+    pub start: Handle<AudioSource>,
+    pub accumulation: Handle<AudioSource>,
+    pub fall: Handle<AudioSource>,
+    pub success: Handle<AudioSource>,
+}
+
+// 一次性音效
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Event)]
+pub enum GameSound {
+    Start,
+    Success,
+    Fall,
+    // 领取奖励平台的加分，加快播放成功音效，音调更高
+    Bonus,
+}
+
+// 蓄力期间循环播放的音效
+#[derive(Debug, Component)]
+pub struct AccumulationSound;
+
+pub fn setup_game_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(GameSounds {
+        start: asset_server.load("sounds/start.mp3"),
+        accumulation: asset_server.load("sounds/accumulation.mp3"),
+        fall: asset_server.load("sounds/fall.mp3"),
+        success: asset_server.load("sounds/success.mp3"),
+    });
+}
+
+pub fn play_game_sounds(
+    mut commands: Commands,
+    mut events: EventReader<GameSound>,
+    game_sounds: Res<GameSounds>,
+) {
+    for sound in events.read() {
+        let (handle, speed) = match sound {
+            GameSound::Start => (&game_sounds.start, 1.0),
+            GameSound::Success => (&game_sounds.success, 1.0),
+            GameSound::Fall => (&game_sounds.fall, 1.0),
+            GameSound::Bonus => (&game_sounds.success, 1.6),
+        };
+        commands.spawn((
+            AudioPlayer(handle.clone()),
+            PlaybackSettings::DESPAWN.with_speed(speed),
+        ));
+    }
+}
+
+// 开始蓄力时播放蓄力音效，结束蓄力时停止
+pub fn sync_accumulation_sound(
+    mut commands: Commands,
+    accumulator: Res<Accumulator>,
+    game_sounds: Res<GameSounds>,
+    q_accumulation_sound: Query<Entity, With<AccumulationSound>>,
+) {
+    match accumulator.0 {
+        Some(_) if q_accumulation_sound.is_empty() => {
+            commands.spawn((
+                AccumulationSound,
+                AudioPlayer(game_sounds.accumulation.clone()),
+                PlaybackSettings::LOOP,
+            ));
+        }
+        None => {
+            for sound in &q_accumulation_sound {
+                commands.entity(sound).despawn();
+            }
+        }
+        _ => {}
+    }
+}
+
+// 暂停时暂停蓄力音效
+pub fn pause_accumulation_sound(q_accumulation_sound: Query<&AudioSink, With<AccumulationSound>>) {
+    for sink in &q_accumulation_sound {
+        sink.pause();
+    }
+}
+
+pub fn resume_accumulation_sound(q_accumulation_sound: Query<&AudioSink, With<AccumulationSound>>) {
+    for sink in &q_accumulation_sound {
+        sink.play();
+    }
+}
@@ -0,0 +1,343 @@
+use bevy::prelude::*;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rand_distr::{Distribution, Normal};
+use std::fmt;
+use std::time::Duration;
+
+use crate::config::GameConfig;
+use crate::input::GameAction;
+use crate::platform::{move_platforms, MovingPlatform, NextPlatform, PlatformShape};
+use crate::player::{
+    player_jump, tick_accumulator, Accumulator, FallState, JumpState, Player, PrepareJumpTimer,
+    PLAYER_HALF_HEIGHT,
+};
+use crate::sim::{ideal_charge, Jump, Rules, Simulation};
+use crate::ui::{toggle_pause, GameMode, GameState};
+
+// 技能为0时蓄力误差的标准差，秒
+pub const MAX_CHARGE_ERROR_SECS: f32 = 0.2;
+// 主菜单闲置多久后开始自动演示，秒
+pub const ATTRACT_DELAY_SECS: f32 = 15.0;
+// 无界面连续游玩时每局的最大跳数，防止完美机器人永不结束
+pub const MAX_BOT_JUMPS: u32 = 10_000;
+// 移动平台预计在落地时刻偏离运动中心小于该距离时才开始蓄力
+pub const MOVING_TIMING_TOLERANCE: f32 = 0.05;
+
+// 自动游玩的机器人，用于主菜单演示
+pub struct BotPlugin;
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Autoplay>()
+            .insert_resource(AttractTimer(Timer::from_seconds(
+                ATTRACT_DELAY_SECS,
+                TimerMode::Once,
+            )))
+            .add_systems(OnEnter(GameState::MainMenu), reset_attract_timer)
+            .add_systems(
+                Update,
+                start_attract_mode.run_if(in_state(GameState::MainMenu)),
+            )
+            .add_systems(
+                Update,
+                (
+                    drive_bot
+                        .after(tick_accumulator)
+                        .after(move_platforms)
+                        .before(player_jump),
+                    exit_attract_mode.after(toggle_pause),
+                )
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(resource_equals(GameMode::Autoplay)),
+            )
+            .add_systems(OnEnter(GameState::GameOver), exit_attract_mode_on_game_over);
+    }
+}
+
+// 机器人以理想蓄力时长加上高斯误差起跳，技能越低误差越大
+#[derive(Debug, Clone)]
+pub struct Bot {
+    // 0到1，1时总是完美落地
+    pub skill: f32,
+    rng: ChaCha8Rng,
+}
+impl Bot {
+    pub fn new(skill: f32, seed: u64) -> Self {
+        Self {
+            skill,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    // 按技能在理想蓄力时长上叠加误差
+    pub fn charge(&mut self, ideal_secs: f32) -> f32 {
+        let std_dev = (1.0 - self.skill.clamp(0.0, 1.0)) * MAX_CHARGE_ERROR_SECS;
+        if std_dev == 0.0 {
+            return ideal_secs;
+        }
+        let error = Normal::new(0.0, std_dev).unwrap().sample(&mut self.rng);
+        (ideal_secs + error).max(0.0)
+    }
+
+    // 在无界面模拟中跳一次，遇到移动平台时等到落地时刻平台恰好回到运动中心
+    pub fn jump(&mut self, sim: &mut Simulation) -> Option<Jump> {
+        let charge = self.charge(sim.perfect_charge());
+        let wait = sim.perfect_wait(charge);
+        sim.dwell(wait);
+        sim.jump_at(charge, wait + charge)
+    }
+
+    // 在无界面模拟中一直跳到游戏结束或达到max_jumps跳
+    pub fn play(&mut self, sim: &mut Simulation, max_jumps: u32) {
+        while !sim.game_over && sim.jumps < max_jumps {
+            self.jump(sim);
+        }
+    }
+}
+
+// 多局无界面游戏的统计
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BotStats {
+    pub games: u32,
+    pub total_score: u64,
+    pub best_score: u32,
+    pub worst_score: u32,
+    pub total_jumps: u64,
+    // 达到最大跳数仍未结束的局数
+    pub unfinished: u32,
+}
+impl BotStats {
+    pub fn record(&mut self, sim: &Simulation) {
+        self.worst_score = if self.games == 0 {
+            sim.score
+        } else {
+            self.worst_score.min(sim.score)
+        };
+        self.best_score = self.best_score.max(sim.score);
+        self.games += 1;
+        self.total_score += sim.score as u64;
+        self.total_jumps += sim.jumps as u64;
+        if !sim.game_over {
+            self.unfinished += 1;
+        }
+    }
+    pub fn mean_score(&self) -> f32 {
+        self.total_score as f32 / self.games.max(1) as f32
+    }
+    pub fn mean_jumps(&self) -> f32 {
+        self.total_jumps as f32 / self.games.max(1) as f32
+    }
+}
+impl fmt::Display for BotStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "games: {}  score: mean {:.1} best {} worst {}  jumps: mean {:.1}  unfinished: {}",
+            self.games,
+            self.mean_score(),
+            self.best_score,
+            self.worst_score,
+            self.mean_jumps(),
+            self.unfinished
+        )
+    }
+}
+
+// 机器人连续游玩games局，指定种子时第i局使用seed+i，否则随机
+pub fn play_games(bot: &mut Bot, rules: &Rules, games: u32, seed: Option<u64>) -> BotStats {
+    let mut stats = BotStats::default();
+    for game in 0..games {
+        let seed = seed.map_or_else(
+            || rand::thread_rng().gen(),
+            |seed| seed.wrapping_add(game as u64),
+        );
+        let mut sim = Simulation::new(seed, rules.clone());
+        bot.play(&mut sim, MAX_BOT_JUMPS);
+        stats.record(&sim);
+    }
+    stats
+}
+
+// 自动演示使用的机器人和本跳的目标蓄力时长
+#[derive(Debug, Resource)]
+pub struct Autoplay {
+    pub bot: Bot,
+    pub charge: Option<Duration>,
+}
+impl Default for Autoplay {
+    fn default() -> Self {
+        Self::new(0.8)
+    }
+}
+impl Autoplay {
+    pub fn new(skill: f32) -> Self {
+        Self {
+            bot: Bot::new(skill, rand::thread_rng().gen()),
+            charge: None,
+        }
+    }
+}
+
+// 主菜单闲置计时
+#[derive(Debug, Resource)]
+pub struct AttractTimer(pub Timer);
+
+pub fn reset_attract_timer(mut attract_timer: ResMut<AttractTimer>) {
+    attract_timer.0.reset();
+}
+
+// 主菜单闲置一段时间后开始自动演示，有任何输入时重新计时
+pub fn start_attract_mode(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut attract_timer: ResMut<AttractTimer>,
+    mut game_mode: ResMut<GameMode>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    if keys.get_pressed().next().is_some() || mouse_buttons.get_pressed().next().is_some() {
+        attract_timer.0.reset();
+        return;
+    }
+    attract_timer.0.tick(time.delta());
+    if attract_timer.0.just_finished() {
+        info!("Start attract mode");
+        *game_mode = GameMode::Autoplay;
+        next_game_state.set(GameState::Playing);
+    }
+}
+
+// 自动演示中玩家有任何输入时回到主菜单
+pub fn exit_attract_mode(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut game_mode: ResMut<GameMode>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    if keys.get_just_pressed().next().is_some() || mouse_buttons.get_just_pressed().next().is_some()
+    {
+        *game_mode = GameMode::Normal;
+        next_game_state.set(GameState::MainMenu);
+    }
+}
+
+// 演示局结束后直接回到主菜单，等待下一次演示
+pub fn exit_attract_mode_on_game_over(
+    mut game_mode: ResMut<GameMode>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    if *game_mode == GameMode::Autoplay {
+        *game_mode = GameMode::Normal;
+        next_game_state.set(GameState::MainMenu);
+    }
+}
+
+// 由角色和下一平台的位置计算理想蓄力时长，按下蓄力键并在达到目标时长时松开
+// 下一平台移动时，等到预计落地时刻平台回到运动中心附近才开始蓄力
+#[allow(clippy::too_many_arguments)]
+pub fn drive_bot(
+    mut actions: ResMut<ButtonInput<GameAction>>,
+    mut accumulator: ResMut<Accumulator>,
+    mut autoplay: ResMut<Autoplay>,
+    config: Res<GameConfig>,
+    prepare_jump_timer: Res<PrepareJumpTimer>,
+    q_player: Query<(&Transform, &JumpState, &FallState), With<Player>>,
+    q_next_platform: Query<
+        (&Transform, &PlatformShape, Option<&MovingPlatform>),
+        With<NextPlatform>,
+    >,
+) {
+    let Ok((player, jump_state, fall_state)) = q_player.get_single() else {
+        return;
+    };
+    match (accumulator.0, autoplay.charge) {
+        (None, planned) => {
+            if !(prepare_jump_timer.0.finished() && jump_state.completed && fall_state.completed) {
+                return;
+            }
+            let Ok((next, next_shape, next_moving)) = q_next_platform.get_single() else {
+                return;
+            };
+            let next_pos = next_moving.map_or(next.translation, |moving| moving.origin);
+            let charge = planned.unwrap_or_else(|| {
+                let ideal = ideal_charge(&config.jump, player.translation, next_pos);
+                Duration::from_secs_f32(autoplay.bot.charge(ideal))
+            });
+            autoplay.charge = Some(charge);
+            if let Some(moving) = next_moving {
+                let charge_secs = charge.as_secs_f32();
+                let height = next_shape.top_y(next_pos) + PLAYER_HALF_HEIGHT - player.translation.y;
+                let touchdown =
+                    moving.elapsed + charge_secs + config.jump.flight_time(charge_secs, height);
+                if moving.motion.offset(touchdown).length() > MOVING_TIMING_TOLERANCE {
+                    return;
+                }
+            }
+            actions.press(GameAction::Charge);
+        }
+        (Some(elapsed), Some(charge)) if elapsed >= charge => {
+            accumulator.0 = Some(charge);
+            autoplay.charge = None;
+            actions.release(GameAction::Charge);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{read_rules, JumpOutcome};
+    use std::path::Path;
+
+    #[test]
+    fn error_grows_as_skill_drops() {
+        let mut perfect = Bot::new(1.0, 7);
+        assert_eq!(perfect.charge(0.8), 0.8);
+
+        let mean_error = |skill| {
+            let mut bot = Bot::new(skill, 7);
+            (0..1000)
+                .map(|_| (bot.charge(1.0) - 1.0).abs())
+                .sum::<f32>()
+                / 1000.0
+        };
+        assert!(mean_error(0.9) < mean_error(0.2));
+    }
+
+    #[test]
+    fn stats_summarize_games() {
+        let mut bot = Bot::new(0.0, 7);
+        let stats = play_games(&mut bot, &Rules::default(), 20, Some(1));
+        assert_eq!(stats.games, 20);
+        assert_eq!(stats.unfinished, 0);
+        assert!(stats.worst_score <= stats.best_score);
+        assert!(stats.total_jumps >= 20);
+
+        let mut perfect = Bot::new(1.0, 7);
+        let stats = play_games(&mut perfect, &Rules::default(), 1, Some(1));
+        assert_eq!(stats.unfinished, 1);
+    }
+
+    // 完美机器人按默认规则长时间游玩，每一跳都应落到下一平台，否则说明生成了无法通过的平台
+    #[test]
+    fn default_difficulty_curve_is_always_winnable() {
+        let rules = read_rules(Path::new("assets"), "config/default.game.ron");
+        assert!(rules.curve.is_some());
+        assert!(rules.platforms.types.len() > 2);
+        let mut bot = Bot::new(1.0, 0);
+        for seed in 0..50 {
+            let mut sim = Simulation::new(seed, rules.clone());
+            while sim.jumps < 200 {
+                let jump = bot.jump(&mut sim).unwrap();
+                assert!(
+                    matches!(jump.outcome, JumpOutcome::Landed { .. }),
+                    "seed {seed} jump {}: {:?}",
+                    sim.jumps,
+                    jump.outcome
+                );
+            }
+        }
+    }
+}
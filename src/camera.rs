@@ -1,9 +1,23 @@
+use crate::config::GameConfig;
 use crate::player::{FallState, JumpState, Player, INITIAL_PLAYER_POS};
+use crate::ui::GameState;
 use bevy::core_pipeline::bloom::Bloom;
 use bevy::prelude::*;
 
+// 相机相对角色的默认位置，可在GameConfig中调整
 pub const INITIAL_CAMERA_POS: Vec3 = Vec3::new(-5.0, 8.0, 5.0);
 
+// 相机、灯光和地面
+pub struct CameraPlugin;
+
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraMoveState>()
+            .add_systems(Startup, (setup_camera, setup_ground))
+            .add_systems(Update, move_camera.run_if(in_state(GameState::Playing)));
+    }
+}
+
 #[derive(Debug, Resource)]
 pub struct CameraMoveState {
     step: Vec3,
@@ -19,7 +33,7 @@ impl Default for CameraMoveState {
     }
 }
 
-pub fn setup_camera(mut commands: Commands) {
+pub fn setup_camera(mut commands: Commands, config: Res<GameConfig>) {
     // 方向光
     // TODO 阴影
     commands.spawn((
@@ -34,7 +48,7 @@ pub fn setup_camera(mut commands: Commands) {
     // camera
     commands.spawn((
         Camera3d::default(),
-        Transform::from_translation(INITIAL_CAMERA_POS).looking_at(Vec3::ZERO, Vec3::Y),
+        Transform::from_translation(config.camera_offset).looking_at(Vec3::ZERO, Vec3::Y),
         Camera {
             hdr: true,
             ..default()
@@ -63,29 +77,30 @@ pub fn setup_ground(
 
 // 相机跟随玩家
 pub fn move_camera(
-    q_player: Query<&Transform, With<Player>>,
+    q_player: Query<(&Transform, &JumpState, &FallState), With<Player>>,
     mut q_camera: Query<&mut Transform, (With<Camera>, Without<Player>)>,
     mut camera_move_state: ResMut<CameraMoveState>,
-    jump_state: Res<JumpState>,
-    fall_state: Res<FallState>,
+    config: Res<GameConfig>,
 ) {
+    let Ok((player, jump_state, fall_state)) = q_player.get_single() else {
+        return;
+    };
     // 跳跃或摔落期间不移动相机
     if jump_state.completed && fall_state.completed {
-        let player = q_player.single();
         let mut camera = q_camera.single_mut();
-        let camera_destination = INITIAL_CAMERA_POS + player.translation;
+        let camera_destination = config.camera_offset + player.translation;
 
         // 检测player是否移动，重新计算step
         if camera_move_state.player_pos.distance(player.translation) > 0.1 {
             let delta = camera_destination - camera.translation;
-            camera_move_state.step = 0.05 * delta;
+            camera_move_state.step = config.camera_follow_rate * delta;
             camera_move_state.player_pos = player.translation;
         }
 
         if camera.translation.distance(camera_destination)
             > Vec3::ZERO.distance(camera_move_state.step)
         {
-            camera.translation = camera.translation + camera_move_state.step;
+            camera.translation += camera_move_state.step;
         }
     }
 }
@@ -0,0 +1,182 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::f32::consts::FRAC_PI_2;
+
+use crate::camera::INITIAL_CAMERA_POS;
+use crate::platform::PlatformLimits;
+use crate::player::JumpPhysics;
+
+// 可调的手感参数，从assets/config/default.game.ron加载，启用dev特性时修改文件后游戏中即时生效
+// 文件中缺省的字段使用默认值
+#[derive(Debug, Clone, Deserialize, Asset, TypePath, Resource)]
+#[serde(default)]
+pub struct GameConfig {
+    pub jump: JumpPhysics,
+    // 蓄力时角色每秒压扁、变宽的比例
+    pub player_squash_rate: f32,
+    pub player_stretch_rate: f32,
+    // 蓄力时平台每秒压扁的比例
+    pub platform_squash_rate: f32,
+    // 摔落时的下坠速度
+    pub fall_speed: f32,
+    // 倾倒时的角速度，弧度每秒
+    pub tilt_speed: f32,
+    // 进入游戏后多久才能起跳，防止点击菜单时误触
+    pub prepare_jump_secs: f32,
+    // 相机相对角色的位置
+    pub camera_offset: Vec3,
+    // 相机每帧追上剩余距离的比例
+    pub camera_follow_rate: f32,
+    // 难度曲线未加载时的平台生成参数
+    pub platform: PlatformLimits,
+    // 练习模式下蓄力时是否显示跳跃轨迹
+    pub practice_trajectory: bool,
+}
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self {
+            jump: JumpPhysics::default(),
+            player_squash_rate: 0.15,
+            player_stretch_rate: 0.12,
+            platform_squash_rate: 0.15,
+            fall_speed: 0.7,
+            tilt_speed: FRAC_PI_2,
+            prepare_jump_secs: 0.2,
+            camera_offset: INITIAL_CAMERA_POS,
+            camera_follow_rate: 0.05,
+            platform: PlatformLimits::default(),
+            practice_trajectory: true,
+        }
+    }
+}
+
+impl GameConfig {
+    // 解析RON格式的配置，跳跃物理参数或平台参数无效时整个配置无效
+    pub fn from_ron(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let config: Self = ron::de::from_bytes(bytes)?;
+        config
+            .jump
+            .validate()
+            .map_err(|err| format!("jump: {err}"))?;
+        config
+            .platform
+            .validate()
+            .map_err(|err| format!("platform: {err}"))?;
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Resource)]
+pub struct GameConfigHandle(pub Handle<GameConfig>);
+
+#[derive(Default)]
+pub struct GameConfigLoader;
+
+impl AssetLoader for GameConfigLoader {
+    type Asset = GameConfig;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        // 加载失败时保留之前的配置
+        GameConfig::from_ron(&bytes).inspect_err(|err| {
+            warn!("Invalid game config, keeping the previous one: {err}");
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["game.ron"]
+    }
+}
+
+// 配置文件在assets目录下的路径，可由命令行指定
+#[derive(Debug, Resource)]
+pub struct GameConfigPath(pub String);
+impl Default for GameConfigPath {
+    fn default() -> Self {
+        Self("config/default.game.ron".to_string())
+    }
+}
+
+pub fn load_game_config(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config_path: Res<GameConfigPath>,
+) {
+    commands.insert_resource(GameConfigHandle(asset_server.load(&config_path.0)));
+}
+
+// 配置加载完成或文件被修改后替换当前配置，加载完成前使用默认配置
+pub fn apply_game_config(
+    mut events: EventReader<AssetEvent<GameConfig>>,
+    configs: Res<Assets<GameConfig>>,
+    config_handle: Res<GameConfigHandle>,
+    mut config: ResMut<GameConfig>,
+) {
+    for event in events.read() {
+        if event.is_loaded_with_dependencies(&config_handle.0)
+            || event.is_modified(&config_handle.0)
+        {
+            if let Some(loaded) = configs.get(&config_handle.0) {
+                info!("Game config applied");
+                *config = loaded.clone();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_config_asset_parses() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/assets/config/default.game.ron"
+        );
+        let config = GameConfig::from_ron(&std::fs::read(path).unwrap()).unwrap();
+        let default = GameConfig::default();
+        assert_eq!(
+            config.jump.distance_per_charge,
+            default.jump.distance_per_charge
+        );
+        assert_eq!(config.camera_offset, default.camera_offset);
+        assert_eq!(config.platform.max_gap, default.platform.max_gap);
+    }
+
+    #[test]
+    fn invalid_jump_physics_is_rejected() {
+        for jump in [
+            "gravity: 0.0",
+            "gravity: -20.0",
+            "distance_per_charge: 0.0",
+            "min_vertical_speed: 0.0",
+            "vertical_speed_per_charge: -1.0",
+        ] {
+            let ron = format!("(jump: ({jump}))");
+            let err = GameConfig::from_ron(ron.as_bytes()).unwrap_err();
+            assert!(err.to_string().starts_with("jump"), "{jump}");
+        }
+        assert!(GameConfig::from_ron(b"(jump: (gravity: 30.0))").is_ok());
+    }
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let config: GameConfig = ron::de::from_str("(fall_speed: 1.5)").unwrap();
+        assert_eq!(config.fall_speed, 1.5);
+        assert_eq!(
+            config.prepare_jump_secs,
+            GameConfig::default().prepare_jump_secs
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use bevy::prelude::*;
+use bevy::utils::SystemTime;
+use serde::{Deserialize, Serialize};
+use std::time::UNIX_EPOCH;
+
+use crate::save::SaveData;
+use crate::ui::{GameMode, Score};
+
+// 某一天的每日挑战记录
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct DailyRecord {
+    pub best: u32,
+    // 当天是否已用掉计分机会
+    pub attempted: bool,
+}
+
+// 每日挑战，所有玩家同一天玩到相同的平台序列，每天只有一次计分机会
+#[derive(Debug, Default, Resource)]
+pub struct DailyChallenge {
+    // 当前挑战的日期，自1970-01-01起的天数(UTC)
+    pub day: u32,
+    // 本局是否计分，用掉计分机会后的重试只作为练习
+    pub scored: bool,
+}
+
+// 今天是自1970-01-01起的第几天(UTC)
+pub fn today() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| (elapsed.as_secs() / 86400) as u32)
+}
+
+// 由日期得到当天的平台种子
+pub fn daily_seed(day: u32) -> u64 {
+    // "jumpjump"
+    0x6a75_6d70_6a75_6d70 ^ day as u64
+}
+
+// 将天数格式化为YYYY-MM-DD
+pub fn format_day(day: u32) -> String {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = day as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + if m <= 2 { 1 } else { 0 };
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+// 每日挑战开局，当天第一次开局计分，之后的重试不计分
+pub fn start_daily_run(
+    game_mode: Res<GameMode>,
+    mut daily_challenge: ResMut<DailyChallenge>,
+    mut save_data: ResMut<SaveData>,
+) {
+    if *game_mode != GameMode::Daily {
+        return;
+    }
+    let day = today();
+    let record = save_data.daily.entry(day).or_default();
+    let scored = !record.attempted;
+    // 开局即用掉计分机会并立即存档，中途退出也算一次
+    record.attempted = true;
+    save_data.store();
+    daily_challenge.day = day;
+    daily_challenge.scored = scored;
+    info!(
+        "Daily challenge {} ({})",
+        format_day(day),
+        if scored { "scored" } else { "practice" }
+    );
+}
+
+// 游戏结束时记录每日挑战的最高分
+pub fn record_daily_score(
+    game_mode: Res<GameMode>,
+    score: Res<Score>,
+    daily_challenge: Res<DailyChallenge>,
+    mut save_data: ResMut<SaveData>,
+) {
+    if *game_mode != GameMode::Daily || !daily_challenge.scored {
+        return;
+    }
+    let record = save_data.daily.entry(daily_challenge.day).or_default();
+    record.best = record.best.max(score.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_day_converts_to_civil_date() {
+        assert_eq!(format_day(0), "1970-01-01");
+        assert_eq!(format_day(11016), "2000-02-29");
+        assert_eq!(format_day(20744), "2026-10-18");
+    }
+
+    #[test]
+    fn daily_seed_differs_per_day() {
+        assert_eq!(daily_seed(20744), daily_seed(20744));
+        assert_ne!(daily_seed(20744), daily_seed(20745));
+    }
+}
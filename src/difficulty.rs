@@ -0,0 +1,195 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext, LoadState};
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::config::GameConfig;
+use crate::platform::PlatformLimits;
+use crate::ui::Score;
+
+// 难度曲线，分数介于两个关键帧之间时线性插值
+#[derive(Debug, Clone, Deserialize, Asset, TypePath)]
+pub struct DifficultyCurve {
+    pub levels: Vec<DifficultyLevel>,
+}
+
+// 难度关键帧，分数达到score时使用limits
+#[derive(Debug, Clone, Deserialize)]
+pub struct DifficultyLevel {
+    pub score: u32,
+    pub limits: PlatformLimits,
+}
+
+impl DifficultyCurve {
+    // 解析RON格式的曲线，关键帧按分数排序，有范围颠倒的关键帧时整条曲线无效
+    pub fn from_ron(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let mut curve: Self = ron::de::from_bytes(bytes)?;
+        curve.levels.sort_by_key(|level| level.score);
+        for level in &curve.levels {
+            level
+                .limits
+                .validate()
+                .map_err(|err| format!("difficulty level at score {}: {err}", level.score))?;
+        }
+        Ok(curve)
+    }
+    // 当前分数对应的平台参数
+    pub fn sample(&self, score: u32) -> Option<PlatformLimits> {
+        let next = self.levels.iter().position(|level| level.score > score);
+        match next {
+            Some(0) => self.levels.first().map(|level| level.limits.clone()),
+            Some(i) => {
+                let (from, to) = (&self.levels[i - 1], &self.levels[i]);
+                let t = (score - from.score) as f32 / (to.score - from.score) as f32;
+                Some(from.limits.lerp(&to.limits, t))
+            }
+            None => self.levels.last().map(|level| level.limits.clone()),
+        }
+    }
+}
+
+#[derive(Debug, Resource)]
+pub struct DifficultyCurveHandle(pub Handle<DifficultyCurve>);
+
+#[derive(Default)]
+pub struct DifficultyCurveLoader;
+
+impl AssetLoader for DifficultyCurveLoader {
+    type Asset = DifficultyCurve;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        // 加载失败时保留之前的曲线
+        DifficultyCurve::from_ron(&bytes).inspect_err(|err| {
+            warn!("Invalid difficulty curve, keeping the previous one: {err}");
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["difficulty.ron"]
+    }
+}
+
+pub fn load_difficulty_curve(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(DifficultyCurveHandle(
+        asset_server.load("config/default.difficulty.ron"),
+    ));
+}
+
+// 难度曲线加载完成或失败后才生成平台，保证同一种子总是得到相同的赛道
+pub fn difficulty_curve_ready(
+    asset_server: Res<AssetServer>,
+    curves: Res<Assets<DifficultyCurve>>,
+    curve_handle: Res<DifficultyCurveHandle>,
+) -> bool {
+    curves.contains(&curve_handle.0)
+        || matches!(
+            asset_server.load_state(&curve_handle.0),
+            LoadState::Failed(_)
+        )
+}
+
+// 随分数调整平台生成参数，曲线未加载完成时使用配置中的参数
+pub fn update_platform_limits(
+    score: Res<Score>,
+    curves: Res<Assets<DifficultyCurve>>,
+    curve_handle: Res<DifficultyCurveHandle>,
+    config: Res<GameConfig>,
+    mut platform_limits: ResMut<PlatformLimits>,
+) {
+    *platform_limits = curves
+        .get(&curve_handle.0)
+        .and_then(|curve| curve.sample(score.0))
+        .unwrap_or_else(|| config.platform.clone());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(score: u32, min_gap: f32, moving_chance: f32) -> DifficultyLevel {
+        DifficultyLevel {
+            score,
+            limits: PlatformLimits {
+                min_gap,
+                moving_chance,
+                ..default()
+            },
+        }
+    }
+
+    #[test]
+    fn sample_interpolates_between_levels() {
+        let curve = DifficultyCurve {
+            levels: vec![level(10, 2.0, 0.1), level(30, 3.0, 0.3)],
+        };
+        assert_eq!(curve.sample(0).unwrap().min_gap, 2.0);
+        assert_eq!(curve.sample(10).unwrap().min_gap, 2.0);
+        let middle = curve.sample(20).unwrap();
+        assert!((middle.min_gap - 2.5).abs() < 1e-5);
+        assert!((middle.moving_chance - 0.2).abs() < 1e-5);
+        assert_eq!(curve.sample(100).unwrap().min_gap, 3.0);
+        assert!(DifficultyCurve { levels: vec![] }.sample(5).is_none());
+    }
+
+    #[test]
+    fn weight_scales_blend_between_levels() {
+        let mut from = level(0, 2.0, 0.0);
+        from.limits.weight_scales.insert("star".to_string(), 0.0);
+        let mut to = level(10, 2.0, 0.0);
+        to.limits.weight_scales.insert("cylinder".to_string(), 3.0);
+        let curve = DifficultyCurve {
+            levels: vec![from, to],
+        };
+        let middle = curve.sample(5).unwrap();
+        assert!((middle.weight_scale("star") - 0.5).abs() < 1e-5);
+        assert!((middle.weight_scale("cylinder") - 2.0).abs() < 1e-5);
+        assert_eq!(middle.weight_scale("box"), 1.0);
+    }
+
+    #[test]
+    fn inverted_range_is_rejected() {
+        let ron = b"(levels: [(score: 0, limits: ()), (score: 10, limits: (min_gap: 5.0, max_gap: 3.0))])";
+        let err = DifficultyCurve::from_ron(ron).unwrap_err();
+        assert!(err.to_string().contains("score 10"));
+        assert!(DifficultyCurve::from_ron(b"(levels: [(score: 0, limits: ())])").is_ok());
+    }
+
+    #[test]
+    fn invalid_limits_are_rejected() {
+        for (limits, field) in [
+            ("direction_spread: -10.0", "direction_spread"),
+            ("direction_spread: NaN", "direction_spread"),
+            ("moving_amplitude: -0.5", "moving_amplitude"),
+            ("moving_period: 0.0", "moving_period"),
+        ] {
+            let ron = format!("(levels: [(score: 0, limits: ({limits}))])");
+            let err = DifficultyCurve::from_ron(ron.as_bytes()).unwrap_err();
+            assert!(err.to_string().contains(field), "{limits}");
+        }
+    }
+
+    #[test]
+    fn default_curve_asset_parses() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/assets/config/default.difficulty.ron"
+        );
+        let curve: DifficultyCurve = ron::de::from_bytes(&std::fs::read(path).unwrap()).unwrap();
+        assert!(!curve.levels.is_empty());
+        for level in &curve.levels {
+            let limits = &level.limits;
+            assert!(limits.min_gap <= limits.max_gap);
+            assert!(limits.min_size <= limits.max_size);
+            assert!(limits.min_height <= limits.max_height);
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use bevy::math::Vec3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, ToSocketAddrs};
+
+use crate::platform::{PlatformMotion, PlatformShape};
+use crate::sim::{CoursePlatform, JumpOutcome, Rules, Simulation};
+
+// 供强化学习训练使用的环境，基于无界面模拟，不受帧率限制
+// 协议为每行一个JSON请求，每个请求回复一行JSON：
+// {"cmd":"reset","seed":42}   -> {"observation":{...}}
+// {"cmd":"step","charge":0.8,"wait":0.0,"target":0} -> {"observation":{...},"reward":2,"done":false,"outcome":"landed"}
+// 站在奖励平台上wait足够长时，reward包含停留奖励；落到分支平台时，reward包含分支平台的额外加分
+// 请求无法解析时回复 {"error":"..."}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Request {
+    // 开始新的一局，未指定种子时随机
+    Reset {
+        #[serde(default)]
+        seed: Option<u64>,
+    },
+    // 下一平台出现wait秒后开始蓄力，蓄力charge秒后朝序号为target的候选平台起跳
+    Step {
+        charge: f32,
+        #[serde(default)]
+        wait: f32,
+        #[serde(default)]
+        target: usize,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Observation {
+    // 下一平台中心相对角色的水平偏移[x, z]
+    pub next_offset: [f32; 2],
+    // 下一平台站立高度与角色当前高度之差
+    pub next_height: f32,
+    pub current: PlatformShape,
+    pub next: PlatformShape,
+    // 下一平台为移动平台时的运动，每步开始时位于运动中心
+    pub next_motion: Option<PlatformMotion>,
+    // 全部候选平台，第一个即next描述的常规平台
+    pub candidates: Vec<CandidateObservation>,
+    pub score: u32,
+    pub combo: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CandidateObservation {
+    // 平台中心相对角色的水平偏移[x, z]
+    pub offset: [f32; 2],
+    // 站立高度与角色当前高度之差
+    pub height: f32,
+    pub shape: PlatformShape,
+    pub motion: Option<PlatformMotion>,
+    // 落上去的额外加分
+    pub reward: u32,
+}
+impl CandidateObservation {
+    fn new(platform: &CoursePlatform, player_pos: Vec3) -> Self {
+        let offset = platform.pos - player_pos;
+        Self {
+            offset: [offset.x, offset.z],
+            height: platform.stand_y() - player_pos.y,
+            shape: platform.shape.clone(),
+            motion: platform.motion,
+            reward: platform.reward,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepOutcome {
+    Stayed,
+    Landed,
+    Perfect,
+    Fell,
+}
+impl From<JumpOutcome> for StepOutcome {
+    fn from(outcome: JumpOutcome) -> Self {
+        match outcome {
+            JumpOutcome::Stayed => Self::Stayed,
+            JumpOutcome::Landed { perfect: false } => Self::Landed,
+            JumpOutcome::Landed { perfect: true } => Self::Perfect,
+            JumpOutcome::Fell { .. } => Self::Fell,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum Response {
+    Reset {
+        observation: Observation,
+    },
+    Step {
+        observation: Observation,
+        // 本跳得分，与游戏中Score的增量一致
+        reward: u32,
+        done: bool,
+        // 游戏已结束时为空
+        outcome: Option<StepOutcome>,
+    },
+    Error {
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Env {
+    rules: Rules,
+    sim: Simulation,
+}
+impl Env {
+    pub fn new(rules: Rules) -> Self {
+        let sim = Simulation::new(0, rules.clone());
+        Self { rules, sim }
+    }
+
+    pub fn reset(&mut self, seed: Option<u64>) -> Observation {
+        let seed = seed.unwrap_or_else(|| rand::thread_rng().gen());
+        self.sim = Simulation::new(seed, self.rules.clone());
+        self.observation()
+    }
+
+    pub fn step(&mut self, charge: f32, wait: f32, target: usize) -> Response {
+        let score = self.sim.score;
+        let charge = charge.max(0.0);
+        let wait = wait.max(0.0);
+        self.sim.dwell(wait);
+        self.sim.aim(target);
+        let outcome = self
+            .sim
+            .jump_at(charge, wait + charge)
+            .map(|jump| jump.outcome.into());
+        Response::Step {
+            observation: self.observation(),
+            reward: self.sim.score - score,
+            done: self.sim.game_over,
+            outcome,
+        }
+    }
+
+    pub fn observation(&self) -> Observation {
+        let sim = &self.sim;
+        let candidates: Vec<_> = sim
+            .candidates
+            .iter()
+            .map(|platform| CandidateObservation::new(platform, sim.player_pos))
+            .collect();
+        let next = candidates[0].clone();
+        Observation {
+            next_offset: next.offset,
+            next_height: next.height,
+            current: sim.current.shape.clone(),
+            next: next.shape,
+            next_motion: next.motion,
+            candidates,
+            score: sim.score,
+            combo: sim.combo,
+        }
+    }
+
+    // 处理一行请求
+    pub fn handle(&mut self, line: &str) -> Response {
+        match serde_json::from_str(line) {
+            Ok(Request::Reset { seed }) => Response::Reset {
+                observation: self.reset(seed),
+            },
+            Ok(Request::Step {
+                charge,
+                wait,
+                target,
+            }) => self.step(charge, wait, target),
+            Err(err) => Response::Error {
+                error: err.to_string(),
+            },
+        }
+    }
+
+    // 逐行读取请求并回复，直到输入结束
+    pub fn serve(&mut self, reader: impl BufRead, mut writer: impl Write) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            serde_json::to_writer(&mut writer, &self.handle(&line))?;
+            writeln!(writer)?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    // 监听TCP端口，每个连接使用独立的一局，可同时训练多个智能体
+    pub fn serve_tcp(&self, addr: impl ToSocketAddrs) -> io::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let mut env = self.clone();
+            std::thread::spawn(move || {
+                let reader = BufReader::new(stream.try_clone()?);
+                env.serve(reader, stream)
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn perfect_step_rewards_score() {
+        let mut env = Env::new(Rules::default());
+        env.reset(Some(7));
+        let charge = env.sim.perfect_charge();
+        let Response::Step {
+            reward,
+            done,
+            outcome,
+            observation,
+        } = env.step(charge, 0.0, 0)
+        else {
+            panic!("expected step response");
+        };
+        assert_eq!(reward, 2);
+        assert!(!done);
+        assert_eq!(outcome, Some(StepOutcome::Perfect));
+        assert_eq!(observation.score, 2);
+    }
+
+    #[test]
+    fn serves_json_lines() {
+        let mut env = Env::new(Rules::default());
+        let input =
+            "{\"cmd\":\"reset\",\"seed\":7}\n{\"cmd\":\"step\",\"charge\":10.0}\nnot json\n";
+        let mut output = Vec::new();
+        env.serve(input.as_bytes(), &mut output).unwrap();
+
+        let lines: Vec<serde_json::Value> = String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[0]["observation"]["score"], 0);
+        assert!(lines[0]["observation"]["next"]["kind"].is_string());
+        assert!(lines[0]["observation"]["next"]["footprint"].is_string());
+        assert_eq!(lines[0]["observation"]["candidates"][0]["reward"], 0);
+        assert_eq!(lines[1]["done"], true);
+        assert_eq!(lines[1]["outcome"], "fell");
+        assert!(lines[2]["error"].is_string());
+    }
+}
@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+use std::time::Duration;
+
+use crate::config::GameConfig;
+use crate::difficulty::{DifficultyCurve, DifficultyCurveHandle};
+use crate::platform::{reset_platform_rng, GameSeed};
+use crate::player::{
+    player_jump, ChargeReleased, FallState, JumpState, INITIAL_PLAYER_POS, PLAYER_RADIUS,
+};
+use crate::registry::{current_registry, PlatformRegistry, PlatformRegistryHandle};
+use crate::replay::{Replay, ReplayRecorder};
+use crate::save::{record_run, RunStats, SaveData};
+use crate::sim::{JumpOutcome, Rules, Simulation};
+use crate::ui::{GameMode, GameState, NewGameSet};
+
+// 个人最佳记录的幽灵，玩家每跳一次，幽灵就重现最佳一局中同一序号的一跳
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ChargeReleased>()
+            .init_resource::<BestReplay>()
+            .add_systems(Startup, load_best_replay)
+            .add_systems(OnEnter(GameState::MainMenu), clear_ghost)
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    clear_ghost,
+                    setup_ghost.after(clear_ghost).after(reset_platform_rng),
+                )
+                    .in_set(NewGameSet),
+            )
+            .add_systems(
+                Update,
+                ghost_jump
+                    .after(player_jump)
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                update_best_replay.after(record_run),
+            );
+    }
+}
+
+// 最高分那一局的录像
+#[derive(Debug, Default, Resource)]
+pub struct BestReplay(pub Option<Replay>);
+
+// 幽灵跳跃者，与玩家共用跳跃和摔落动画
+#[derive(Debug, Component)]
+pub struct Ghost {
+    pub replay: Replay,
+    // 幽灵所在的无界面赛道，第一跳时创建，此时难度曲线和平台类型表已加载
+    pub sim: Option<Simulation>,
+}
+
+pub fn load_best_replay(save_data: Res<SaveData>, mut best_replay: ResMut<BestReplay>) {
+    best_replay.0 = Replay::path(&save_data, "best").and_then(|path| Replay::load(&path).ok());
+}
+
+pub fn update_best_replay(
+    run_stats: Res<RunStats>,
+    recorder: Res<ReplayRecorder>,
+    mut best_replay: ResMut<BestReplay>,
+) {
+    if run_stats.new_record {
+        best_replay.0 = Some(recorder.0.clone());
+    }
+}
+
+// 只有赛道与最佳一局相同时才有参考意义，回放时不显示
+pub fn setup_ghost(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    best_replay: Res<BestReplay>,
+    game_seed: Res<GameSeed>,
+    game_mode: Res<GameMode>,
+) {
+    if *game_mode == GameMode::Replay {
+        return;
+    }
+    let Some(replay) = best_replay
+        .0
+        .as_ref()
+        .filter(|replay| replay.seed == game_seed.current)
+    else {
+        return;
+    };
+    commands.spawn((
+        Mesh3d(meshes.add(Capsule3d::new(PLAYER_RADIUS, 0.5).mesh())),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgba(1.0, 0.75, 0.8, 0.35),
+            alpha_mode: AlphaMode::Blend,
+            ..default()
+        })),
+        Transform::from_translation(INITIAL_PLAYER_POS),
+        JumpState::default(),
+        FallState::default(),
+        Ghost {
+            replay: replay.clone(),
+            sim: None,
+        },
+    ));
+}
+
+// 玩家起跳时幽灵按最佳一局同一序号的蓄力时长起跳
+pub fn ghost_jump(
+    mut events: EventReader<ChargeReleased>,
+    config: Res<GameConfig>,
+    curves: Res<Assets<DifficultyCurve>>,
+    curve_handle: Res<DifficultyCurveHandle>,
+    registries: Res<Assets<PlatformRegistry>>,
+    registry_handle: Res<PlatformRegistryHandle>,
+    mut q_ghost: Query<(&Transform, &mut Ghost, &mut JumpState, &mut FallState)>,
+) {
+    for _ in events.read() {
+        for (transform, mut ghost, mut jump_state, mut fall_state) in &mut q_ghost {
+            let ghost = &mut *ghost;
+            let seed = ghost.replay.seed;
+            let sim = ghost.sim.get_or_insert_with(|| {
+                let rules = Rules {
+                    physics: config.jump.clone(),
+                    curve: curves.get(&curve_handle.0).cloned(),
+                    platforms: current_registry(&registries, &registry_handle),
+                };
+                Simulation::new(seed, rules)
+            });
+            let index = sim.jumps as usize;
+            let (Some(charge), Some(release), Some(wait), Some(target)) = (
+                ghost.replay.charges.get(index),
+                ghost.replay.releases.get(index),
+                ghost.replay.waits.get(index),
+                ghost.replay.targets.get(index),
+            ) else {
+                continue;
+            };
+            let charge_secs = Duration::from_nanos(*charge).as_secs_f32();
+            sim.dwell(*wait);
+            sim.aim(*target as usize);
+            // 最佳一局已结束
+            let Some(jump) = sim.jump_at(charge_secs, *release) else {
+                continue;
+            };
+            jump_state.falled = false;
+            if let JumpOutcome::Fell { tilt } = jump.outcome {
+                jump_state.falled = true;
+                match tilt {
+                    Some(direction) => fall_state.animate_tilt_fall(jump.landing_pos, direction),
+                    None => fall_state.animate_straight_fall(jump.landing_pos),
+                }
+            }
+            jump_state.animate_jump(
+                transform.translation,
+                jump.landing_pos,
+                config.jump.vertical_speed(charge_secs),
+                config.jump.gravity,
+            );
+        }
+    }
+}
+
+pub fn clear_ghost(mut commands: Commands, q_ghost: Query<Entity, With<Ghost>>) {
+    for ghost in &q_ghost {
+        commands.entity(ghost).despawn();
+    }
+}
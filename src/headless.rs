@@ -0,0 +1,22 @@
+use bevy::asset::AssetPlugin;
+use bevy::audio::AudioSource;
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::window::WindowFocused;
+
+// 配合MinimalPlugins在没有窗口、音频设备和GPU的环境下运行游戏
+// 只注册游戏系统用到的资源类型和事件，不做任何渲染和播放
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((AssetPlugin::default(), InputPlugin, StatesPlugin))
+            .init_asset::<Mesh>()
+            .init_asset::<StandardMaterial>()
+            .init_asset::<Image>()
+            .init_asset::<Font>()
+            .init_asset::<AudioSource>()
+            .add_event::<WindowFocused>();
+    }
+}
@@ -0,0 +1,157 @@
+use bevy::input::touch::Touches;
+use bevy::prelude::*;
+
+use crate::ui::GameMode;
+
+// 游戏操作，由键盘、鼠标、触屏和手柄输入映射而来
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GameAction {
+    // 蓄力，按下开始蓄力，松开起跳
+    Charge,
+    // 菜单上一个按钮
+    MenuPrev,
+    // 菜单下一个按钮
+    MenuNext,
+    // 确认菜单按钮
+    MenuConfirm,
+    // 暂停/继续
+    Pause,
+    // 瞄准左边的候选平台
+    AimLeft,
+    // 瞄准右边的候选平台
+    AimRight,
+}
+
+// 各操作绑定的按键
+#[derive(Debug, Resource)]
+pub struct InputBindings {
+    pub charge_keys: Vec<KeyCode>,
+    pub charge_mouse_buttons: Vec<MouseButton>,
+    pub charge_gamepad_buttons: Vec<GamepadButton>,
+    // 是否任意触摸都算蓄力
+    pub charge_touch: bool,
+    pub menu_prev_keys: Vec<KeyCode>,
+    pub menu_next_keys: Vec<KeyCode>,
+    pub menu_confirm_keys: Vec<KeyCode>,
+    pub menu_prev_gamepad_buttons: Vec<GamepadButton>,
+    pub menu_next_gamepad_buttons: Vec<GamepadButton>,
+    pub menu_confirm_gamepad_buttons: Vec<GamepadButton>,
+    pub pause_keys: Vec<KeyCode>,
+    pub pause_gamepad_buttons: Vec<GamepadButton>,
+    pub aim_left_keys: Vec<KeyCode>,
+    pub aim_right_keys: Vec<KeyCode>,
+    pub aim_left_gamepad_buttons: Vec<GamepadButton>,
+    pub aim_right_gamepad_buttons: Vec<GamepadButton>,
+}
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            charge_keys: vec![KeyCode::Space],
+            charge_mouse_buttons: vec![MouseButton::Left],
+            charge_gamepad_buttons: vec![GamepadButton::South],
+            charge_touch: true,
+            menu_prev_keys: vec![KeyCode::ArrowUp, KeyCode::ArrowLeft],
+            menu_next_keys: vec![KeyCode::ArrowDown, KeyCode::ArrowRight, KeyCode::Tab],
+            menu_confirm_keys: vec![KeyCode::Enter, KeyCode::NumpadEnter],
+            menu_prev_gamepad_buttons: vec![GamepadButton::DPadUp, GamepadButton::DPadLeft],
+            menu_next_gamepad_buttons: vec![GamepadButton::DPadDown, GamepadButton::DPadRight],
+            menu_confirm_gamepad_buttons: vec![GamepadButton::South],
+            pause_keys: vec![KeyCode::Escape],
+            pause_gamepad_buttons: vec![GamepadButton::Start],
+            aim_left_keys: vec![KeyCode::ArrowLeft, KeyCode::KeyA],
+            aim_right_keys: vec![KeyCode::ArrowRight, KeyCode::KeyD],
+            aim_left_gamepad_buttons: vec![GamepadButton::DPadLeft],
+            aim_right_gamepad_buttons: vec![GamepadButton::DPadRight],
+        }
+    }
+}
+
+// 将设备输入映射为GameAction
+// 只在设备按下/松开的那一帧改变操作状态，其他来源(如测试)直接press/release的操作不会被覆盖
+// 回放时蓄力和瞄准只由录像驱动，设备只能操作菜单和暂停
+pub fn map_game_actions(
+    mut actions: ResMut<ButtonInput<GameAction>>,
+    bindings: Res<InputBindings>,
+    game_mode: Res<GameMode>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    gamepads: Query<&Gamepad>,
+) {
+    actions.clear();
+    let replaying = *game_mode == GameMode::Replay;
+
+    // 蓄力
+    let charge_just_pressed = keys.any_just_pressed(bindings.charge_keys.iter().copied())
+        || mouse_buttons.any_just_pressed(bindings.charge_mouse_buttons.iter().copied())
+        || (bindings.charge_touch && touches.any_just_pressed())
+        || gamepads.iter().any(|gamepad| {
+            gamepad.any_just_pressed(bindings.charge_gamepad_buttons.iter().copied())
+        });
+    let charge_just_released = keys.any_just_released(bindings.charge_keys.iter().copied())
+        || mouse_buttons.any_just_released(bindings.charge_mouse_buttons.iter().copied())
+        || (bindings.charge_touch && (touches.any_just_released() || touches.any_just_canceled()))
+        || gamepads.iter().any(|gamepad| {
+            gamepad.any_just_released(bindings.charge_gamepad_buttons.iter().copied())
+        });
+    let charge_held = keys.any_pressed(bindings.charge_keys.iter().copied())
+        || mouse_buttons.any_pressed(bindings.charge_mouse_buttons.iter().copied())
+        || (bindings.charge_touch && touches.iter().next().is_some())
+        || gamepads
+            .iter()
+            .any(|gamepad| gamepad.any_pressed(bindings.charge_gamepad_buttons.iter().copied()));
+    // 快速点按时按下和松开可能在同一帧，两者都要处理
+    if charge_just_pressed && !replaying {
+        actions.press(GameAction::Charge);
+    }
+    if charge_just_released && !charge_held && !replaying {
+        // 多个设备同时按住时，全部松开才算松开
+        actions.release(GameAction::Charge);
+    }
+
+    // 菜单导航、暂停和瞄准只需要按下事件
+    for (action, keys_bound, gamepad_buttons_bound) in [
+        (
+            GameAction::MenuPrev,
+            &bindings.menu_prev_keys,
+            &bindings.menu_prev_gamepad_buttons,
+        ),
+        (
+            GameAction::MenuNext,
+            &bindings.menu_next_keys,
+            &bindings.menu_next_gamepad_buttons,
+        ),
+        (
+            GameAction::MenuConfirm,
+            &bindings.menu_confirm_keys,
+            &bindings.menu_confirm_gamepad_buttons,
+        ),
+        (
+            GameAction::Pause,
+            &bindings.pause_keys,
+            &bindings.pause_gamepad_buttons,
+        ),
+        (
+            GameAction::AimLeft,
+            &bindings.aim_left_keys,
+            &bindings.aim_left_gamepad_buttons,
+        ),
+        (
+            GameAction::AimRight,
+            &bindings.aim_right_keys,
+            &bindings.aim_right_gamepad_buttons,
+        ),
+    ] {
+        if replaying && matches!(action, GameAction::AimLeft | GameAction::AimRight) {
+            continue;
+        }
+        let just_pressed = keys.any_just_pressed(keys_bound.iter().copied())
+            || gamepads
+                .iter()
+                .any(|gamepad| gamepad.any_just_pressed(gamepad_buttons_bound.iter().copied()));
+        if just_pressed {
+            actions.press(action);
+            actions.release(action);
+        }
+    }
+}
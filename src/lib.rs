@@ -0,0 +1,96 @@
+use bevy::app::PluginGroupBuilder;
+use bevy::input::InputSystem;
+use bevy::prelude::*;
+
+use audio::AudioPlugin;
+use bot::BotPlugin;
+use camera::CameraPlugin;
+use config::*;
+use daily::*;
+use ghost::GhostPlugin;
+use input::*;
+use platform::{GameSeed, PlatformPlugin};
+use player::PlayerPlugin;
+use practice::PracticePlugin;
+use replay::ReplayPlugin;
+use save::*;
+use ui::*;
+
+pub mod audio;
+pub mod bot;
+pub mod camera;
+pub mod config;
+pub mod daily;
+pub mod difficulty;
+pub mod env;
+pub mod ghost;
+pub mod headless;
+pub mod input;
+pub mod platform;
+pub mod player;
+pub mod practice;
+pub mod registry;
+pub mod replay;
+pub mod save;
+pub mod sim;
+pub mod ui;
+
+// 游戏核心：状态、输入映射、计分和存档，各子系统插件都依赖它
+// 未预先插入GameSeed和SaveData时使用随机种子和内存存档
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<GameState>()
+            .init_resource::<ButtonInput<GameAction>>()
+            .init_resource::<InputBindings>()
+            .init_resource::<Resuming>()
+            .init_resource::<GameSeed>()
+            .init_resource::<GameMode>()
+            .init_resource::<DailyChallenge>()
+            .init_resource::<SaveData>()
+            .init_resource::<RunStats>()
+            .init_resource::<GameConfig>()
+            .init_resource::<GameConfigPath>()
+            .init_asset::<GameConfig>()
+            .init_asset_loader::<GameConfigLoader>()
+            .add_systems(Startup, load_game_config)
+            .add_systems(Update, apply_game_config)
+            .insert_resource(Score(0))
+            .insert_resource(Combo(0))
+            .insert_resource(ScoreUpQueue(Vec::new()))
+            .configure_sets(OnEnter(GameState::Playing), NewGameSet.run_if(not_resuming))
+            .add_systems(PreUpdate, map_game_actions.after(InputSystem))
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    (start_daily_run, reset_score, reset_run_stats).in_set(NewGameSet),
+                    finish_resuming.after(NewGameSet),
+                ),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                (record_daily_score, record_run.after(record_daily_score)),
+            );
+    }
+}
+
+// 完整的游戏，不包含窗口、渲染、音频设备等底层插件
+// 子系统可以通过PluginGroupBuilder单独禁用或替换
+pub struct JumpJumpPlugin;
+
+impl PluginGroup for JumpJumpPlugin {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(GamePlugin)
+            .add(PlayerPlugin)
+            .add(PlatformPlugin)
+            .add(CameraPlugin)
+            .add(UiPlugin)
+            .add(AudioPlugin)
+            .add(ReplayPlugin)
+            .add(GhostPlugin)
+            .add(BotPlugin)
+            .add(PracticePlugin)
+    }
+}
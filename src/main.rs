@@ -1,102 +1,148 @@
-use std::time::Duration;
-
-use crate::camera::*;
-use crate::platform::*;
-use crate::player::*;
-use crate::ui::*;
 use bevy::prelude::*;
+use bevy::window::WindowMode;
 use bevy_hanabi::prelude::*;
+use clap::{Parser, ValueEnum};
+use jump_jump::audio::AudioPlugin;
+use jump_jump::bot::*;
+use jump_jump::config::GameConfigPath;
+use jump_jump::env::Env;
+use jump_jump::platform::*;
+use jump_jump::player::*;
+use jump_jump::replay::*;
+use jump_jump::save::*;
+use jump_jump::sim::read_rules;
+use jump_jump::ui::{GameMode, GameState};
+use jump_jump::JumpJumpPlugin;
+use std::path::{Path, PathBuf};
+
+// 启动参数，由main转换为资源和插件设置
+#[derive(Debug, Parser)]
+#[command(version, about = "Jump Jump")]
+struct Cli {
+    /// Fixed platform seed
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Start a game in this mode right away instead of showing the main menu
+    #[arg(long, value_enum)]
+    mode: Option<ModeArg>,
+    /// Play back a replay file
+    #[arg(long)]
+    replay: Option<PathBuf>,
+    /// Game config file, relative to the assets directory
+    #[arg(long)]
+    config: Option<String>,
+    /// Window width in logical pixels
+    #[arg(long, default_value_t = 1280.0)]
+    width: f32,
+    /// Window height in logical pixels
+    #[arg(long, default_value_t = 720.0)]
+    height: f32,
+    /// Borderless fullscreen on the primary monitor
+    #[arg(long)]
+    fullscreen: bool,
+    /// Disable all game sounds
+    #[arg(long)]
+    mute: bool,
+    /// Bot skill from 0 to 1, used by autoplay and --bot-games
+    #[arg(long, default_value_t = 0.8)]
+    skill: f32,
+    /// Let the bot play N games without a window and print statistics
+    #[arg(long, value_name = "N")]
+    bot_games: Option<u32>,
+    /// Run as a reinforcement learning environment on `stdio` or a TCP address
+    #[arg(long, value_name = "stdio|ADDR")]
+    env: Option<String>,
+}
 
-mod camera;
-mod platform;
-mod player;
-mod ui;
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ModeArg {
+    Normal,
+    Daily,
+    Autoplay,
+    Practice,
+}
+impl From<ModeArg> for GameMode {
+    fn from(mode: ModeArg) -> Self {
+        match mode {
+            ModeArg::Normal => GameMode::Normal,
+            ModeArg::Daily => GameMode::Daily,
+            ModeArg::Autoplay => GameMode::Autoplay,
+            ModeArg::Practice => GameMode::Practice,
+        }
+    }
+}
 
 fn main() {
+    let cli = Cli::parse();
+    let config_path = cli
+        .config
+        .map_or_else(GameConfigPath::default, GameConfigPath);
+
+    // 不启动游戏，作为强化学习环境运行
+    if let Some(endpoint) = cli.env {
+        let mut env = Env::new(read_rules(Path::new("assets"), &config_path.0));
+        let result = if endpoint == "stdio" {
+            env.serve(std::io::stdin().lock(), std::io::stdout().lock())
+        } else {
+            env.serve_tcp(endpoint.as_str())
+        };
+        if let Err(err) = result {
+            eprintln!("Environment stopped: {err}");
+        }
+        return;
+    }
+
+    // 不启动游戏，由机器人连续游玩并输出统计
+    if let Some(games) = cli.bot_games {
+        let rules = read_rules(Path::new("assets"), &config_path.0);
+        let mut bot = Bot::new(cli.skill, rand::random());
+        println!("{}", play_games(&mut bot, &rules, games, cli.seed));
+        return;
+    }
+
     let mut app = App::new();
-    app.add_plugins(DefaultPlugins);
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(Window {
+            resolution: (cli.width, cli.height).into(),
+            mode: if cli.fullscreen {
+                WindowMode::BorderlessFullscreen(MonitorSelection::Primary)
+            } else {
+                WindowMode::Windowed
+            },
+            ..default()
+        }),
+        ..default()
+    }));
 
     #[cfg(not(target_arch = "wasm32"))]
     {
         app.add_plugins(HanabiPlugin);
     }
 
-    app.init_state::<GameState>()
-        .insert_resource(CameraMoveState::default())
-        .insert_resource(Score(0))
-        .insert_resource(Accumulator(None))
-        .insert_resource(JumpState::default())
-        .insert_resource(FallState::default())
-        .insert_resource(GenerateAccumulationParticleEffectTimer(Timer::new(
-            Duration::from_millis(200),
-            TimerMode::Once,
-        )))
-        .insert_resource(PrepareJumpTimer(Timer::new(
-            Duration::from_millis(200),
-            TimerMode::Once,
-        )))
-        .insert_resource(ScoreUpQueue(Vec::new()))
-        .add_systems(Startup, (setup_camera, setup_ground, setup_game_sounds))
-        // Main Menu
-        .add_systems(
-            OnEnter(GameState::MainMenu),
-            (
-                setup_main_menu,
-                clear_player,
-                clear_platforms,
-                despawn_scoreboard,
-            ),
-        )
-        .add_systems(
-            Update,
-            (click_button,).run_if(in_state(GameState::MainMenu)),
-        )
-        .add_systems(
-            OnExit(GameState::MainMenu),
-            (despawn_screen::<OnMainMenuScreen>,),
-        )
-        // Playing
-        .add_systems(
-            OnEnter(GameState::Playing),
-            (
-                clear_player,
-                clear_platforms,
-                despawn_scoreboard,
-                setup_first_platform.after(clear_platforms),
-                setup_player.after(clear_player),
-                setup_scoreboard.after(despawn_scoreboard),
-                reset_score,
-                reset_prepare_jump_timer,
-            ),
-        )
-        .add_systems(
-            Update,
-            (
-                prepare_jump,
-                generate_next_platform,
-                move_camera,
-                player_jump,
-                update_scoreboard,
-                animate_jump,
-                animate_fall,
-                animate_player_accumulation,
-                animate_platform_accumulation.after(player_jump),
-                spawn_score_up_effect,
-                sync_score_up_effect,
-                shift_score_up_effect,
-            )
-                .run_if(in_state(GameState::Playing)),
-        )
-        // GameOver
-        .add_systems(OnEnter(GameState::GameOver), (setup_game_over_menu,))
-        .add_systems(
-            Update,
-            (click_button,).run_if(in_state(GameState::GameOver)),
-        )
-        .add_systems(
-            OnExit(GameState::GameOver),
-            (despawn_screen::<OnGameOverMenuScreen>,),
-        );
+    let mut game = JumpJumpPlugin.build();
+    if cli.mute {
+        game = game.disable::<AudioPlugin>();
+    }
+    app.insert_resource(GameSeed::new(cli.seed))
+        .insert_resource(SaveData::load())
+        .insert_resource(config_path)
+        .insert_resource(Autoplay::new(cli.skill))
+        .add_plugins(game);
+
+    if let Some(mode) = cli.mode {
+        app.insert_resource(GameMode::from(mode))
+            .insert_state(GameState::Playing);
+    }
+    if let Some(path) = cli.replay {
+        match Replay::load(&path) {
+            Ok(replay) => {
+                app.insert_resource(GameMode::Replay)
+                    .insert_resource(ReplayPlayback::new(replay))
+                    .insert_state(GameState::Playing);
+            }
+            Err(err) => error!("Failed to load replay {}: {err}", path.display()),
+        }
+    }
 
     #[cfg(not(target_arch = "wasm32"))]
     {
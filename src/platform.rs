@@ -1,128 +1,647 @@
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use rand::Rng;
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+use bevy::render::render_asset::RenderAssetUsages;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, PI, TAU};
 
-use crate::player::Accumulator;
+use crate::audio::GameSound;
+use crate::config::GameConfig;
+use crate::daily::{daily_seed, start_daily_run, DailyChallenge};
+use crate::difficulty::{
+    difficulty_curve_ready, load_difficulty_curve, update_platform_limits, DifficultyCurve,
+    DifficultyCurveLoader,
+};
+use crate::player::{player_jump, player_land, Accumulator, FallState, JumpState, Player, Scoring};
+use crate::registry::{
+    current_registry, load_platform_registry, platform_registry_ready, triangulate, Footprint,
+    PlatformRegistry, PlatformRegistryHandle, PlatformRegistryLoader, PlatformType,
+};
+use crate::replay::ReplayPlayback;
+use crate::sim::{first_platform, next_candidates, CoursePlatform};
+use crate::ui::{
+    spawn_score_up_effect, GameMode, GameState, NewGameSet, Score, ScoreUpEvent, ScoreUpQueue,
+};
+
+// 平台的生成、难度和特效
+pub struct PlatformPlugin;
+
+impl Plugin for PlatformPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PlatformLimits>()
+            .init_resource::<PlatformRng>()
+            .init_asset::<DifficultyCurve>()
+            .init_asset_loader::<DifficultyCurveLoader>()
+            .init_asset::<PlatformRegistry>()
+            .init_asset_loader::<PlatformRegistryLoader>()
+            .add_systems(Startup, (load_difficulty_curve, load_platform_registry))
+            .add_systems(
+                OnEnter(GameState::MainMenu),
+                (clear_platforms, clear_ripple_effects),
+            )
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    clear_platforms,
+                    clear_ripple_effects,
+                    reset_platform_rng.after(start_daily_run),
+                    setup_first_platform
+                        .after(clear_platforms)
+                        .after(reset_platform_rng),
+                )
+                    .in_set(NewGameSet),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_platform_limits.before(generate_next_platform),
+                    generate_next_platform
+                        .run_if(difficulty_curve_ready)
+                        .run_if(platform_registry_ready),
+                    move_platforms.before(player_jump),
+                    update_target_marker.after(player_land),
+                    animate_platform_accumulation.after(player_jump),
+                    tick_dwell
+                        .after(player_jump)
+                        .after(player_land)
+                        .before(spawn_score_up_effect),
+                    animate_bonus_effect.after(animate_platform_accumulation),
+                    spawn_ripple_effect.before(spawn_score_up_effect),
+                    animate_ripple_effect,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
+// 落点距平台中心小于该值视为完美落地
+pub const PERFECT_LANDING_DISTANCE: f32 = 0.2;
 
 // 当前所站的平台
 #[derive(Debug, Component)]
 pub struct CurrentPlatform;
 
-// 下一个平台
+// 下一个平台，有多个候选平台时为瞄准的那个
 #[derive(Debug, Component)]
 pub struct NextPlatform;
 
+// 下一跳的候选平台，index为生成时的序号，0为常规平台，其余为分支平台
+#[derive(Debug, Component)]
+pub struct Candidate {
+    pub index: usize,
+    // 落上去的额外加分
+    pub reward: u32,
+}
+
+// 有多个候选平台时，瞄准平台上的标记
+#[derive(Debug, Component)]
+pub struct TargetMarker;
+
+// 在奖励平台上停留该时长不起跳可额外加分，秒
+pub const BONUS_DWELL_SECS: f32 = 2.0;
+
+// 角色在当前平台上站立未蓄力的时长，每次起跳后清零
+#[derive(Debug, Default, Component)]
+pub struct Dwell {
+    pub elapsed: f32,
+    // 本平台的奖励已领取
+    pub awarded: bool,
+}
+
+// 领取奖励时平台的弹跳效果
+#[derive(Debug, Component)]
+pub struct BonusEffect(pub Timer);
+
+// 标准平台的边长、高度，第一个平台使用标准尺寸
+pub const PLATFORM_SIZE: f32 = 1.5;
+pub const PLATFORM_HEIGHT: f32 = 1.0;
+
+// 随机平台的生成参数，由难度曲线按分数调整
+#[derive(Debug, Clone, Deserialize, Resource)]
+#[serde(default)]
+pub struct PlatformLimits {
+    // 相邻平台中心的距离
+    pub min_gap: f32,
+    pub max_gap: f32,
+    // 下一平台方向偏离前进方向(+X与-Z的角平分线)的最大角度，度
+    pub direction_spread: f32,
+    // 方形平台边长或圆柱平台直径，多边形平台按此缩放
+    pub min_size: f32,
+    pub max_size: f32,
+    pub min_height: f32,
+    pub max_height: f32,
+    // 下一平台为移动平台的概率
+    pub moving_chance: f32,
+    // 移动平台偏离运动中心的最大距离
+    pub moving_amplitude: f32,
+    // 移动平台往复一次的时长，秒
+    pub moving_period: f32,
+    // 额外给出更远更小的分支平台供选择的概率
+    pub branch_chance: f32,
+    // 按类型名调整平台类型表中的出现权重，缺省的类型倍数为1
+    pub weight_scales: HashMap<String, f32>,
+}
+impl Default for PlatformLimits {
+    fn default() -> Self {
+        Self {
+            min_gap: 2.5,
+            max_gap: 4.0,
+            direction_spread: 45.0,
+            min_size: 1.2,
+            max_size: 1.8,
+            min_height: 0.6,
+            max_height: 1.4,
+            moving_chance: 0.0,
+            moving_amplitude: 0.8,
+            moving_period: 3.0,
+            branch_chance: 0.0,
+            weight_scales: HashMap::new(),
+        }
+    }
+}
+impl PlatformLimits {
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        Self {
+            min_gap: lerp(self.min_gap, other.min_gap),
+            max_gap: lerp(self.max_gap, other.max_gap),
+            direction_spread: lerp(self.direction_spread, other.direction_spread),
+            min_size: lerp(self.min_size, other.min_size),
+            max_size: lerp(self.max_size, other.max_size),
+            min_height: lerp(self.min_height, other.min_height),
+            max_height: lerp(self.max_height, other.max_height),
+            moving_chance: lerp(self.moving_chance, other.moving_chance),
+            moving_amplitude: lerp(self.moving_amplitude, other.moving_amplitude),
+            moving_period: lerp(self.moving_period, other.moving_period),
+            branch_chance: lerp(self.branch_chance, other.branch_chance),
+            weight_scales: self
+                .weight_scales
+                .keys()
+                .chain(other.weight_scales.keys())
+                .map(|name| {
+                    let scale = lerp(self.weight_scale(name), other.weight_scale(name));
+                    (name.clone(), scale)
+                })
+                .collect(),
+        }
+    }
+    // 平台类型出现权重的倍数
+    pub fn weight_scale(&self, name: &str) -> f32 {
+        self.weight_scales.get(name).copied().unwrap_or(1.0)
+    }
+    // 检查各随机范围的下限不大于上限，否则生成平台时会panic
+    pub fn validate(&self) -> Result<(), String> {
+        let ranges = [
+            ("gap", self.min_gap, self.max_gap),
+            ("size", self.min_size, self.max_size),
+            ("height", self.min_height, self.max_height),
+        ];
+        for (name, min, max) in ranges {
+            if min.partial_cmp(&max).is_none_or(|order| order.is_gt()) {
+                return Err(format!(
+                    "min_{name} ({min}) is greater than max_{name} ({max})"
+                ));
+            }
+        }
+        // 负数会使生成平台时的随机范围无效
+        let non_negative = [
+            ("direction_spread", self.direction_spread),
+            ("moving_amplitude", self.moving_amplitude),
+        ];
+        for (name, value) in non_negative {
+            if !value.is_finite() || value < 0.0 {
+                return Err(format!("{name} ({value}) must not be negative"));
+            }
+        }
+        // 移动平台的位置要除以往复周期
+        if !self.moving_period.is_finite() || self.moving_period <= 0.0 {
+            return Err(format!(
+                "moving_period ({}) must be positive",
+                self.moving_period
+            ));
+        }
+        for (name, scale) in &self.weight_scales {
+            if !scale.is_finite() || *scale < 0.0 {
+                return Err(format!("weight scale of {name} ({scale}) is invalid"));
+            }
+        }
+        Ok(())
+    }
+}
+
+// 平台生成所用的随机数种子，相同种子生成相同的平台序列
+#[derive(Debug, Default, Resource)]
+pub struct GameSeed {
+    // 通过命令行指定的种子，每局都使用该种子
+    pub fixed: Option<u64>,
+    // 当前这局使用的种子
+    pub current: u64,
+}
+impl GameSeed {
+    pub fn new(fixed: Option<u64>) -> Self {
+        Self { fixed, current: 0 }
+    }
+}
+
+// 平台生成的随机数发生器，所有平台随机性都取自这里
+#[derive(Debug, Resource)]
+pub struct PlatformRng(pub ChaCha8Rng);
+impl Default for PlatformRng {
+    fn default() -> Self {
+        Self(ChaCha8Rng::seed_from_u64(0))
+    }
+}
+
+// 移动平台沿垂直于跳跃方向的axis往复运动
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PlatformMotion {
+    pub axis: Vec3,
+    pub amplitude: f32,
+    // 往复一次的时长，秒
+    pub period: f32,
+}
+impl PlatformMotion {
+    // 运动elapsed秒后相对运动中心的偏移
+    pub fn offset(&self, elapsed: f32) -> Vec3 {
+        self.axis * self.amplitude * (TAU * elapsed / self.period).sin()
+    }
+}
+
+// 作为下一平台时往复移动，角色落在上面后移除
 #[derive(Debug, Component)]
-pub enum PlatformShape {
-    Box,
-    Cylinder,
+pub struct MovingPlatform {
+    pub motion: PlatformMotion,
+    // 运动中心
+    pub origin: Vec3,
+    // 成为下一平台后已移动的时长，秒
+    pub elapsed: f32,
+}
+
+// 完美落地时平台上的波纹效果
+#[derive(Debug, Component)]
+pub struct RippleEffect(pub Timer);
+
+// 平台实例的形状，由平台类型和随机尺寸决定
+#[derive(Debug, Clone, PartialEq, Component, Serialize)]
+pub struct PlatformShape {
+    // 平台类型名
+    pub kind: String,
+    pub footprint: Footprint,
+    // 方形边长或圆柱直径，多边形轮廓按此缩放
+    pub size: f32,
+    pub height: f32,
+    // 在上面停留BONUS_DWELL_SECS秒不起跳的加分
+    pub bonus: Option<u32>,
 }
 
 impl PlatformShape {
+    pub fn new(kind: &str, footprint: Footprint, size: f32, height: f32) -> Self {
+        Self {
+            kind: kind.to_string(),
+            footprint,
+            size,
+            height,
+            bonus: None,
+        }
+    }
+    pub fn from_type(platform_type: &PlatformType, size: f32, height: f32) -> Self {
+        Self {
+            bonus: platform_type.bonus,
+            ..Self::new(
+                &platform_type.name,
+                platform_type.footprint.clone(),
+                size,
+                height,
+            )
+        }
+    }
     pub fn mesh(&self) -> Mesh {
-        match self {
-            Self::Box => Mesh::from(Cuboid::new(1.5, 1.0, 1.5)),
-            Self::Cylinder => Mesh::from(Cylinder::new(0.75, 1.0)),
+        match &self.footprint {
+            Footprint::Square => Mesh::from(Cuboid::new(self.size, self.height, self.size)),
+            Footprint::Circle => Mesh::from(Cylinder::new(self.size / 2.0, self.height)),
+            Footprint::Polygon { points } => extrude_polygon(points, self.size, self.height),
         }
     }
+    // 平台顶面高度，平台坐标位于几何中心
+    pub fn top_y(&self, platform_pos: Vec3) -> f32 {
+        platform_pos.y + self.height / 2.0
+    }
     // 是否落到平台上
     pub fn is_landed_on_platform(&self, platform_pos: Vec3, landing_pos: Vec3) -> bool {
-        dbg!(platform_pos);
-        dbg!(landing_pos);
-        match self {
-            Self::Box => {
-                (landing_pos.x - platform_pos.x).abs() < 1.5 / 2.0
-                    && (landing_pos.z - platform_pos.z).abs() < 1.5 / 2.0
-            }
-            Self::Cylinder => {
-                (landing_pos.x - platform_pos.x).abs() < 0.75
-                    && (landing_pos.z - platform_pos.z).abs() < 0.75
-            }
-        }
+        self.footprint
+            .contains(self.size, (landing_pos - platform_pos).xz())
+    }
+    // 落点距平台中心的距离
+    pub fn center_distance(&self, platform_pos: Vec3, landing_pos: Vec3) -> f32 {
+        self.footprint
+            .center_distance((landing_pos - platform_pos).xz())
     }
-    // 是否接触到角色
+    // 是否接触到角色，角色底面视为半径为player_radius的圆
     pub fn is_touched_player(
         &self,
         platform_pos: Vec3,
         landing_pos: Vec3,
         player_radius: f32,
     ) -> bool {
-        match self {
-            Self::Box => {
-                (landing_pos.x - platform_pos.x).abs() < (1.5 / 2.0 + player_radius)
-                    && (landing_pos.z - platform_pos.z).abs() < (1.5 / 2.0 + player_radius)
+        self.footprint
+            .distance(self.size, (landing_pos - platform_pos).xz())
+            < player_radius
+    }
+}
+
+// 将多边形轮廓拉伸为柱体网格，各面使用独立顶点以保持棱角
+fn extrude_polygon(points: &[Vec2], size: f32, height: f32) -> Mesh {
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut indices: Vec<u32> = Vec::new();
+    let mut push_triangle = |corners: [Vec3; 3], normal: Vec3| {
+        let [a, mut b, mut c] = corners;
+        // 逆时针为正面
+        if (b - a).cross(c - a).dot(normal) < 0.0 {
+            std::mem::swap(&mut b, &mut c);
+        }
+        let base = positions.len() as u32;
+        positions.extend([a, b, c]);
+        normals.extend([normal; 3]);
+        indices.extend([base, base + 1, base + 2]);
+    };
+    let at = |p: Vec2, y: f32| Vec3::new(p.x * size, y, p.y * size);
+    let (top, bottom) = (height / 2.0, -height / 2.0);
+    for [a, b, c] in triangulate(points) {
+        let (a, b, c) = (points[a], points[b], points[c]);
+        push_triangle([at(a, top), at(b, top), at(c, top)], Vec3::Y);
+        push_triangle([at(a, bottom), at(b, bottom), at(c, bottom)], Vec3::NEG_Y);
+    }
+    let area: f32 = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| a.perp_dot(*b))
+        .sum();
+    for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+        let edge = *b - *a;
+        let outward = if area > 0.0 {
+            -edge.perp()
+        } else {
+            edge.perp()
+        };
+        let normal = Vec3::new(outward.x, 0.0, outward.y).normalize_or_zero();
+        push_triangle([at(*a, top), at(*b, top), at(*b, bottom)], normal);
+        push_triangle([at(*a, top), at(*b, bottom), at(*a, bottom)], normal);
+    }
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_NORMAL, normals)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+// 生成平台实体所需的资源
+#[derive(SystemParam)]
+pub struct PlatformSpawner<'w, 's> {
+    commands: Commands<'w, 's>,
+    meshes: ResMut<'w, Assets<Mesh>>,
+    materials: ResMut<'w, Assets<StandardMaterial>>,
+    asset_server: Res<'w, AssetServer>,
+}
+
+impl PlatformSpawner<'_, '_> {
+    // 平台类型声明了场景时加载glTF场景，否则按轮廓生成网格
+    fn spawn<T: Bundle>(
+        &mut self,
+        rng: &mut ChaCha8Rng,
+        platform: CoursePlatform,
+        platform_type: Option<&PlatformType>,
+        bundle: T,
+    ) {
+        // 固定配色的平台同样消耗随机数
+        let color = rand_platform_color(rng);
+        let color = platform_type
+            .and_then(|platform_type| platform_type.color)
+            .map_or(color, |(r, g, b)| Color::srgb(r, g, b));
+        let scene = platform_type.and_then(|platform_type| platform_type.scene.clone());
+        let decorations = platform_type.map_or(&[][..], |platform_type| &platform_type.decorations);
+        let mut entity = self.commands.spawn((
+            Transform::from_translation(platform.pos),
+            Visibility::default(),
+            bundle,
+        ));
+        match scene {
+            Some(scene) => {
+                let scale = Vec3::new(
+                    platform.shape.size,
+                    platform.shape.height,
+                    platform.shape.size,
+                );
+                entity.with_child((
+                    SceneRoot(self.asset_server.load(scene)),
+                    Transform::from_scale(scale),
+                ));
             }
-            Self::Cylinder => {
-                (landing_pos.x - platform_pos.x).abs() < (0.75 + player_radius)
-                    && (landing_pos.z - platform_pos.z).abs() < (0.75 + player_radius)
+            None => {
+                entity.insert((
+                    Mesh3d(self.meshes.add(platform.shape.mesh())),
+                    MeshMaterial3d(self.materials.add(color)),
+                ));
             }
         }
+        // 装饰件随平台尺寸缩放，放在顶面上
+        let top = Vec3::Y * (platform.shape.height / 2.0);
+        for decoration in decorations {
+            let (r, g, b) = decoration.color;
+            entity.with_child((
+                Mesh3d(self.meshes.add(decoration.shape.mesh())),
+                MeshMaterial3d(self.materials.add(Color::srgb(r, g, b))),
+                Transform::from_translation(top + decoration.offset * platform.shape.size)
+                    .with_scale(Vec3::splat(platform.shape.size)),
+            ));
+        }
+        if let Some(motion) = platform.motion {
+            entity.insert(MovingPlatform {
+                motion,
+                origin: platform.pos,
+                elapsed: 0.0,
+            });
+        }
+        entity.insert(platform.shape);
+    }
+}
+
+// 由平台实体得到赛道平台，移动平台的位置取运动中心
+pub fn course_platform(
+    transform: &Transform,
+    shape: &PlatformShape,
+    moving: Option<&MovingPlatform>,
+) -> CoursePlatform {
+    CoursePlatform {
+        pos: moving.map_or(transform.translation, |moving| moving.origin),
+        shape: shape.clone(),
+        motion: moving.map(|moving| moving.motion),
+        reward: 0,
     }
 }
 
-fn spawn_rand_platform<T: Component>(
+// 按序号排列的候选平台
+pub fn course_candidates<'a>(
+    candidates: impl IntoIterator<
+        Item = (
+            &'a Transform,
+            &'a PlatformShape,
+            Option<&'a MovingPlatform>,
+            &'a Candidate,
+        ),
+    >,
+) -> Vec<CoursePlatform> {
+    let mut candidates: Vec<_> = candidates
+        .into_iter()
+        .map(|(transform, shape, moving, candidate)| {
+            let platform = CoursePlatform {
+                reward: candidate.reward,
+                ..course_platform(transform, shape, moving)
+            };
+            (candidate.index, platform)
+        })
+        .collect();
+    candidates.sort_by_key(|(index, _)| *index);
+    candidates
+        .into_iter()
+        .map(|(_, platform)| platform)
+        .collect()
+}
+
+// 改为瞄准序号为index的候选平台，没有该序号时不变
+pub fn aim_candidate<'a>(
     commands: &mut Commands,
-    meshes: &mut ResMut<Assets<Mesh>>,
-    materials: &mut ResMut<Assets<StandardMaterial>>,
-    pos: Vec3,
-    component: T,
+    candidates: impl IntoIterator<Item = (Entity, &'a Candidate)>,
+    index: usize,
 ) {
-    let platform_shape = rand_platform_shape();
-    commands.spawn((
-        Mesh3d(meshes.add(platform_shape.mesh())),
-        MeshMaterial3d(materials.add(rand_platform_color())),
-        Transform::from_translation(pos),
-        platform_shape,
-        component,
-    ));
+    let candidates: Vec<_> = candidates.into_iter().collect();
+    if !candidates
+        .iter()
+        .any(|(_, candidate)| candidate.index == index)
+    {
+        return;
+    }
+    for (entity, candidate) in candidates {
+        if candidate.index == index {
+            commands.entity(entity).insert(NextPlatform);
+        } else {
+            commands.entity(entity).remove::<NextPlatform>();
+        }
+    }
 }
 
-pub fn setup_first_platform(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<StandardMaterial>>,
+// 每局开始时按种子重置随机数发生器
+// 每日挑战使用当天日期对应的种子，回放使用录像的种子，普通模式未指定种子时随机生成
+pub fn reset_platform_rng(
+    mut game_seed: ResMut<GameSeed>,
+    mut platform_rng: ResMut<PlatformRng>,
+    game_mode: Res<GameMode>,
+    daily_challenge: Res<DailyChallenge>,
+    playback: Res<ReplayPlayback>,
 ) {
-    spawn_rand_platform(
-        &mut commands,
-        &mut meshes,
-        &mut materials,
-        Vec3::new(0.0, 0.5, 0.0),
-        CurrentPlatform,
-    );
+    game_seed.current = match *game_mode {
+        GameMode::Daily => daily_seed(daily_challenge.day),
+        GameMode::Replay => playback.replay.as_ref().map_or(0, |replay| replay.seed),
+        GameMode::Normal | GameMode::Autoplay | GameMode::Practice => {
+            game_seed.fixed.unwrap_or_else(|| rand::thread_rng().gen())
+        }
+    };
+    info!("Platform seed: {}", game_seed.current);
+    platform_rng.0 = ChaCha8Rng::seed_from_u64(game_seed.current);
 }
 
-// 生成下一个平台
+// 第一个平台总是内置的标准平台，不必等待类型表加载
+pub fn setup_first_platform(mut spawner: PlatformSpawner, mut platform_rng: ResMut<PlatformRng>) {
+    let rng = &mut platform_rng.0;
+    let platform = first_platform(rng);
+    spawner.spawn(rng, platform, None, (CurrentPlatform, Dwell::default()));
+}
+
+// 生成下一跳的候选平台，默认瞄准常规平台
+#[allow(clippy::too_many_arguments)]
 pub fn generate_next_platform(
+    mut spawner: PlatformSpawner,
+    platform_limits: Res<PlatformLimits>,
+    mut platform_rng: ResMut<PlatformRng>,
+    score: Res<Score>,
+    registries: Res<Assets<PlatformRegistry>>,
+    registry_handle: Res<PlatformRegistryHandle>,
+    q_current_platform: Query<(&Transform, &PlatformShape), With<CurrentPlatform>>,
+    q_next_platform: Query<Entity, With<NextPlatform>>,
+) {
+    if q_next_platform.is_empty() {
+        let (current_transform, current_shape) = q_current_platform.single();
+        let current = course_platform(current_transform, current_shape, None);
+        let registry = current_registry(&registries, &registry_handle);
+        let rng = &mut platform_rng.0;
+        let candidates = next_candidates(rng, &platform_limits, &registry, score.0, &current);
+        for (index, platform) in candidates.into_iter().enumerate() {
+            let platform_type = registry.get(&platform.shape.kind);
+            let candidate = Candidate {
+                index,
+                reward: platform.reward,
+            };
+            if index == 0 {
+                spawner.spawn(rng, platform, platform_type, (candidate, NextPlatform));
+            } else {
+                spawner.spawn(rng, platform, platform_type, candidate);
+            }
+        }
+    }
+}
+
+// 移动平台只在作为候选平台时移动
+pub fn move_platforms(
+    time: Res<Time>,
+    mut q_moving_platform: Query<(&mut Transform, &mut MovingPlatform), With<Candidate>>,
+) {
+    for (mut transform, mut moving) in &mut q_moving_platform {
+        moving.elapsed += time.delta_secs();
+        transform.translation = moving.origin + moving.motion.offset(moving.elapsed);
+    }
+}
+
+// 有多个候选平台时在瞄准的平台顶面显示一个圆环，随平台一起移动
+pub fn update_target_marker(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    q_current_platform: Query<&Transform, With<CurrentPlatform>>,
-    q_next_platform: Query<Entity, With<NextPlatform>>,
+    q_candidates: Query<(Entity, &PlatformShape, Has<NextPlatform>), With<Candidate>>,
+    q_marker: Query<(Entity, &Parent), With<TargetMarker>>,
 ) {
-    if q_next_platform.is_empty() {
-        let current_platform = &q_current_platform.single();
-        let mut rng = rand::thread_rng();
-        let rand_distance = rng.gen_range(2.5..4.0);
-        let next_pos = if rng.gen_bool(0.5) {
-            Vec3::new(
-                current_platform.translation.x + rand_distance,
-                0.5,
-                current_platform.translation.z,
-            )
+    let target = q_candidates
+        .iter()
+        .find(|(_, _, next)| *next)
+        .filter(|_| q_candidates.iter().count() > 1);
+    let mut marked = false;
+    for (marker, parent) in &q_marker {
+        if target.is_some_and(|(platform, _, _)| platform == parent.get()) {
+            marked = true;
         } else {
-            Vec3::new(
-                current_platform.translation.x,
-                0.5,
-                current_platform.translation.z - rand_distance,
-            )
-        };
-
-        spawn_rand_platform(
-            &mut commands,
-            &mut meshes,
-            &mut materials,
-            next_pos,
-            NextPlatform,
-        );
+            commands.entity(marker).despawn_recursive();
+        }
+    }
+    let Some((platform, shape, _)) = target else {
+        return;
+    };
+    if !marked {
+        commands.entity(platform).with_child((
+            Mesh3d(meshes.add(Annulus::new(0.25, 0.35))),
+            MeshMaterial3d(materials.add(StandardMaterial {
+                base_color: Color::srgba(1.0, 1.0, 1.0, 0.8),
+                alpha_mode: AlphaMode::Blend,
+                unlit: true,
+                ..default()
+            })),
+            Transform::from_xyz(0.0, shape.height / 2.0 + 0.01, 0.0)
+                .with_rotation(Quat::from_rotation_x(-FRAC_PI_2)),
+            TargetMarker,
+        ));
     }
 }
 
@@ -131,12 +650,14 @@ pub fn animate_platform_accumulation(
     accumulator: Res<Accumulator>,
     mut q_current_platform: Query<&mut Transform, With<CurrentPlatform>>,
     time: Res<Time>,
+    config: Res<GameConfig>,
 ) {
     let mut current_platform = q_current_platform.single_mut();
     match accumulator.0 {
         Some(_) => {
-            current_platform.scale.y =
-                (current_platform.scale.y - 0.15 * time.delta_secs()).max(0.6);
+            current_platform.scale.y = (current_platform.scale.y
+                - config.platform_squash_rate * time.delta_secs())
+            .max(0.6);
         }
         None => {
             // TODO 回弹效果
@@ -145,23 +666,289 @@ pub fn animate_platform_accumulation(
     }
 }
 
+// 站在奖励平台上未蓄力时累计停留时长，达到BONUS_DWELL_SECS时加分，每个平台只奖励一次
+#[allow(clippy::type_complexity)]
+pub fn tick_dwell(
+    mut commands: Commands,
+    mut scoring: Scoring,
+    mut sounds: EventWriter<GameSound>,
+    time: Res<Time>,
+    accumulator: Res<Accumulator>,
+    q_player: Query<(&Transform, &JumpState, &FallState), With<Player>>,
+    mut q_current_platform: Query<(Entity, &PlatformShape, &mut Dwell), With<CurrentPlatform>>,
+) {
+    let Ok((player, jump_state, fall_state)) = q_player.get_single() else {
+        return;
+    };
+    let Ok((platform, shape, mut dwell)) = q_current_platform.get_single_mut() else {
+        return;
+    };
+    let standing = jump_state.completed
+        && !jump_state.landing_pending
+        && !jump_state.falled
+        && fall_state.completed;
+    if !standing {
+        return;
+    }
+    if accumulator.0.is_none() {
+        dwell.elapsed += time.delta_secs();
+    }
+    // 回放时按下蓄力键前会把停留时长设为录制时的值，此时仍需判定
+    let Some(points) = shape.bonus else {
+        return;
+    };
+    if dwell.awarded || dwell.elapsed < BONUS_DWELL_SECS {
+        return;
+    }
+    dwell.awarded = true;
+    scoring.score.0 += points;
+    scoring.score_up_queue.0.push(ScoreUpEvent {
+        landing_pos: player.translation + Vec3::Y * 0.5,
+        points,
+        perfect: false,
+    });
+    sounds.send(GameSound::Bonus);
+    commands
+        .entity(platform)
+        .insert(BonusEffect(Timer::from_seconds(0.5, TimerMode::Once)));
+}
+
+// 领取奖励时平台先鼓起再回弹
+pub fn animate_bonus_effect(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut q_platform: Query<(Entity, &mut Transform, &mut BonusEffect)>,
+) {
+    for (entity, mut transform, mut effect) in &mut q_platform {
+        effect.0.tick(time.delta());
+        if effect.0.finished() {
+            transform.scale = Vec3::ONE;
+            commands.entity(entity).remove::<BonusEffect>();
+            continue;
+        }
+        let bulge = (effect.0.fraction() * PI).sin();
+        transform.scale = Vec3::new(1.0 + 0.15 * bulge, 1.0 + 0.3 * bulge, 1.0 + 0.15 * bulge);
+    }
+}
+
+// 完美落地后在平台表面生成波纹
+pub fn spawn_ripple_effect(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    score_up_queue: Res<ScoreUpQueue>,
+    q_player: Query<&JumpState, With<Player>>,
+    q_current_platform: Query<(&Transform, &PlatformShape), With<CurrentPlatform>>,
+) {
+    let landed = q_player
+        .get_single()
+        .is_ok_and(|jump_state| jump_state.completed);
+    if !landed || !score_up_queue.0.iter().any(|event| event.perfect) {
+        return;
+    }
+    let Ok((platform, platform_shape)) = q_current_platform.get_single() else {
+        return;
+    };
+    commands.spawn((
+        Mesh3d(meshes.add(Annulus::new(0.35, 0.45))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::srgba(1.0, 1.0, 1.0, 0.8),
+            alpha_mode: AlphaMode::Blend,
+            unlit: true,
+            ..default()
+        })),
+        Transform::from_xyz(
+            platform.translation.x,
+            platform_shape.top_y(platform.translation) + 0.01,
+            platform.translation.z,
+        )
+        .with_rotation(Quat::from_rotation_x(-FRAC_PI_2)),
+        RippleEffect(Timer::from_seconds(0.6, TimerMode::Once)),
+    ));
+}
+
+// 波纹向外扩散并逐渐消失
+pub fn animate_ripple_effect(
+    mut commands: Commands,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    time: Res<Time>,
+    mut q_ripple: Query<(
+        Entity,
+        &mut Transform,
+        &MeshMaterial3d<StandardMaterial>,
+        &mut RippleEffect,
+    )>,
+) {
+    for (entity, mut transform, material, mut ripple) in &mut q_ripple {
+        ripple.0.tick(time.delta());
+        if ripple.0.finished() {
+            commands.entity(entity).despawn();
+            continue;
+        }
+        let progress = ripple.0.fraction();
+        transform.scale = Vec3::splat(1.0 + 1.5 * progress);
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color.set_alpha(0.8 * (1.0 - progress));
+        }
+    }
+}
+
+pub fn clear_ripple_effects(mut commands: Commands, q_ripple: Query<Entity, With<RippleEffect>>) {
+    for ripple in &q_ripple {
+        commands.entity(ripple).despawn();
+    }
+}
+
 pub fn clear_platforms(mut commands: Commands, q_platforms: Query<Entity, With<PlatformShape>>) {
     for platform in &q_platforms {
-        commands.entity(platform).despawn();
+        commands.entity(platform).despawn_recursive();
     }
 }
 
-fn rand_platform_color() -> Color {
-    let mut rng = rand::thread_rng();
+pub fn rand_platform_color(rng: &mut ChaCha8Rng) -> Color {
     Color::srgb(rng.gen(), rng.gen(), rng.gen())
 }
 
-fn rand_platform_shape() -> PlatformShape {
-    let mut rng = rand::thread_rng();
-    let selection = rng.gen_range(0..2);
-    match selection {
-        0 => PlatformShape::Box,
-        1 => PlatformShape::Cylinder,
-        _ => PlatformShape::Box,
+// 随机尺寸，并从类型表中按权重选择当前分数可出现的平台类型
+pub fn rand_platform_shape(
+    rng: &mut ChaCha8Rng,
+    limits: &PlatformLimits,
+    registry: &PlatformRegistry,
+    score: u32,
+) -> PlatformShape {
+    let size = rng.gen_range(limits.min_size..=limits.max_size);
+    let height = rng.gen_range(limits.min_height..=limits.max_height);
+    PlatformShape::from_type(registry.pick(rng, score, limits), size, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLATFORM_POS: Vec3 = Vec3::new(2.0, 0.5, -3.0);
+
+    fn standard_box() -> PlatformShape {
+        PlatformShape::new("box", Footprint::Square, 1.5, 1.0)
+    }
+
+    fn standard_cylinder() -> PlatformShape {
+        PlatformShape::new("cylinder", Footprint::Circle, 1.5, 1.0)
+    }
+
+    fn landing(x: f32, z: f32) -> Vec3 {
+        PLATFORM_POS + Vec3::new(x, 1.0, z)
+    }
+
+    #[test]
+    fn box_landing_covers_whole_square() {
+        let shape = standard_box();
+        assert!(shape.is_landed_on_platform(PLATFORM_POS, landing(0.0, 0.0)));
+        assert!(shape.is_landed_on_platform(PLATFORM_POS, landing(0.7, 0.7)));
+        assert!(shape.is_landed_on_platform(PLATFORM_POS, landing(-0.7, 0.7)));
+        assert!(!shape.is_landed_on_platform(PLATFORM_POS, landing(0.76, 0.0)));
+        assert!(!shape.is_landed_on_platform(PLATFORM_POS, landing(0.0, -0.76)));
+    }
+
+    #[test]
+    fn cylinder_landing_is_radial() {
+        let shape = standard_cylinder();
+        assert!(shape.is_landed_on_platform(PLATFORM_POS, landing(0.0, 0.0)));
+        assert!(shape.is_landed_on_platform(PLATFORM_POS, landing(0.74, 0.0)));
+        assert!(shape.is_landed_on_platform(PLATFORM_POS, landing(0.5, -0.5)));
+        // 外接正方形的角落不属于圆盘
+        assert!(!shape.is_landed_on_platform(PLATFORM_POS, landing(0.7, 0.7)));
+        assert!(!shape.is_landed_on_platform(PLATFORM_POS, landing(-0.6, -0.6)));
+    }
+
+    #[test]
+    fn box_touch_uses_rounded_corners() {
+        let shape = standard_box();
+        // 沿边缘悬空
+        assert!(shape.is_touched_player(PLATFORM_POS, landing(0.9, 0.0), 0.2));
+        assert!(!shape.is_touched_player(PLATFORM_POS, landing(0.96, 0.0), 0.2));
+        // 角落处按到角点的距离判断
+        assert!(shape.is_touched_player(PLATFORM_POS, landing(0.85, 0.85), 0.2));
+        assert!(!shape.is_touched_player(PLATFORM_POS, landing(0.9, 0.9), 0.2));
+    }
+
+    #[test]
+    fn cylinder_touch_overhangs_rim() {
+        let shape = standard_cylinder();
+        assert!(shape.is_touched_player(PLATFORM_POS, landing(0.9, 0.0), 0.2));
+        assert!(shape.is_touched_player(PLATFORM_POS, landing(-0.65, 0.65), 0.2));
+        assert!(!shape.is_touched_player(PLATFORM_POS, landing(0.0, 0.96), 0.2));
+        // 外接正方形角落附近既落不上也碰不到
+        assert!(!shape.is_touched_player(PLATFORM_POS, landing(0.7, 0.7), 0.2));
+    }
+
+    #[test]
+    fn top_surface_follows_height() {
+        let tall = PlatformShape::new("box", Footprint::Square, 1.5, 1.4);
+        let pos = Vec3::new(0.0, 0.7, 0.0);
+        assert!((tall.top_y(pos) - 1.4).abs() < 1e-5);
+        assert!((standard_cylinder().top_y(PLATFORM_POS) - 1.0).abs() < 1e-5);
+    }
+
+    #[test]
+    fn same_seed_generates_same_shapes() {
+        let limits = PlatformLimits::default();
+        let registry = PlatformRegistry::default();
+        let mut a = ChaCha8Rng::seed_from_u64(42);
+        let mut b = ChaCha8Rng::seed_from_u64(42);
+        for _ in 0..20 {
+            assert_eq!(
+                rand_platform_shape(&mut a, &limits, &registry, 0),
+                rand_platform_shape(&mut b, &limits, &registry, 0)
+            );
+        }
+    }
+
+    #[test]
+    fn center_distance_matches_shape() {
+        assert!(
+            (standard_box().center_distance(PLATFORM_POS, landing(0.3, -0.4)) - 0.4).abs() < 1e-5
+        );
+        assert!(
+            (standard_cylinder().center_distance(PLATFORM_POS, landing(0.3, -0.4)) - 0.5).abs()
+                < 1e-5
+        );
+    }
+
+    #[test]
+    fn shape_takes_bonus_from_type() {
+        let manhole = PlatformType {
+            bonus: Some(5),
+            ..PlatformType::new("manhole", Footprint::Circle)
+        };
+        let shape = PlatformShape::from_type(&manhole, 1.5, 1.0);
+        assert_eq!(shape.bonus, Some(5));
+        assert_eq!(shape.kind, "manhole");
+        assert!(!shape.is_landed_on_platform(PLATFORM_POS, landing(0.7, 0.7)));
+    }
+
+    #[test]
+    fn extruded_polygon_has_outward_normals() {
+        let hexagon: Vec<Vec2> = (0..6)
+            .map(|i| Vec2::from_angle(i as f32 * TAU / 6.0) * 0.5)
+            .collect();
+        let mesh = extrude_polygon(&hexagon, 2.0, 1.0);
+        let Some(bevy::render::mesh::VertexAttributeValues::Float32x3(normals)) =
+            mesh.attribute(Mesh::ATTRIBUTE_NORMAL)
+        else {
+            panic!("missing normals");
+        };
+        let Some(bevy::render::mesh::VertexAttributeValues::Float32x3(positions)) =
+            mesh.attribute(Mesh::ATTRIBUTE_POSITION)
+        else {
+            panic!("missing positions");
+        };
+        // 顶面、底面各4个三角形，侧面12个
+        assert_eq!(positions.len(), 20 * 3);
+        for (position, normal) in positions.iter().zip(normals) {
+            let (position, normal) = (Vec3::from(*position), Vec3::from(*normal));
+            if normal.y == 0.0 {
+                assert!(position.xz().dot(normal.xz()) > 0.0);
+            }
+        }
     }
 }
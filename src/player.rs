@@ -1,62 +1,231 @@
-use bevy::audio::AudioSink;
 use bevy::color::palettes;
+use bevy::ecs::system::SystemParam;
 use bevy::prelude::*;
-use bevy::utils::Instant;
 use bevy_hanabi::prelude::*;
-use std::f32::consts::{FRAC_PI_2, PI, TAU};
-
-use crate::platform::PlatformShape;
-use crate::ui::{GameSounds, GameState, ScoreUpEvent, ScoreUpQueue};
+use serde::Deserialize;
+use std::f32::consts::TAU;
+use std::time::Duration;
+
+use crate::audio::{AccumulationSound, GameSound};
+use crate::config::GameConfig;
+use crate::input::GameAction;
+use crate::platform::{
+    aim_candidate, course_candidates, course_platform, Candidate, Dwell, MovingPlatform,
+    PlatformShape,
+};
+use crate::save::RunStats;
+use crate::sim::{landing_points, resolve_jump, JumpOutcome, FORWARD};
+use crate::ui::{Combo, GameMode, GameState, NewGameSet, ScoreUpEvent, ScoreUpQueue};
 use crate::{
     platform::{CurrentPlatform, NextPlatform},
     ui::Score,
 };
 
+// 角色的蓄力、跳跃和摔落
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GameSound>()
+            .add_event::<ChargeReleased>()
+            .insert_resource(Accumulator(None))
+            .insert_resource(GenerateAccumulationParticleEffectTimer(Timer::new(
+                Duration::from_millis(200),
+                TimerMode::Once,
+            )))
+            .init_resource::<PrepareJumpTimer>()
+            .add_systems(
+                OnEnter(GameState::MainMenu),
+                (clear_player, reset_accumulator),
+            )
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    clear_player,
+                    setup_player.after(clear_player),
+                    reset_prepare_jump_timer,
+                    reset_accumulator,
+                )
+                    .in_set(NewGameSet),
+            )
+            .add_systems(
+                Update,
+                (
+                    prepare_jump,
+                    aim_next_platform
+                        .after(player_land)
+                        .before(player_jump)
+                        .run_if(not(resource_equals(GameMode::Replay))),
+                    tick_accumulator.before(player_jump),
+                    player_jump,
+                    animate_jump,
+                    player_land.after(animate_jump).before(animate_fall),
+                    animate_fall,
+                    animate_player_accumulation,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            );
+    }
+}
+
 pub const INITIAL_PLAYER_POS: Vec3 = Vec3::new(0.0, 1.5, 0.0);
+// 角色胶囊体半径
+pub const PLAYER_RADIUS: f32 = 0.2;
+// 角色站立时中心到脚底的距离
+pub const PLAYER_HALF_HEIGHT: f32 = 0.5;
 
-// 蓄力
+// 蓄力，记录已蓄力时长，暂停期间不累计
 #[derive(Debug, Resource)]
-pub struct Accumulator(pub Option<Instant>);
-
-#[derive(Debug, Component)]
-pub struct AccumulationSound;
+pub struct Accumulator(pub Option<Duration>);
 
-#[derive(Debug, Resource)]
+// 开局时按配置重置
+#[derive(Debug, Default, Resource)]
 pub struct PrepareJumpTimer(pub Timer);
 
-// 跳跃状态
-#[derive(Debug, Resource)]
+// 跳跃物理参数
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct JumpPhysics {
+    // 重力加速度
+    pub gravity: f32,
+    // 每秒蓄力对应的水平跳跃距离
+    pub distance_per_charge: f32,
+    // 最小起跳竖直速度
+    pub min_vertical_speed: f32,
+    // 每秒蓄力增加的起跳竖直速度
+    pub vertical_speed_per_charge: f32,
+}
+impl Default for JumpPhysics {
+    fn default() -> Self {
+        Self {
+            gravity: 20.0,
+            distance_per_charge: 3.0,
+            min_vertical_speed: 6.5,
+            vertical_speed_per_charge: 2.5,
+        }
+    }
+}
+impl JumpPhysics {
+    // 重力和起跳速度必须为正，否则滞空时长为0或无解，跳跃轨迹为NaN
+    pub fn validate(&self) -> Result<(), String> {
+        let positive = [
+            ("gravity", self.gravity),
+            ("distance_per_charge", self.distance_per_charge),
+            ("min_vertical_speed", self.min_vertical_speed),
+        ];
+        for (name, value) in positive {
+            if !value.is_finite() || value <= 0.0 {
+                return Err(format!("{name} ({value}) must be positive"));
+            }
+        }
+        if !self.vertical_speed_per_charge.is_finite() || self.vertical_speed_per_charge < 0.0 {
+            return Err(format!(
+                "vertical_speed_per_charge ({}) must not be negative",
+                self.vertical_speed_per_charge
+            ));
+        }
+        Ok(())
+    }
+    // 蓄力时长对应的水平跳跃距离
+    pub fn jump_distance(&self, charge_secs: f32) -> f32 {
+        self.distance_per_charge * charge_secs
+    }
+    // 蓄力时长对应的起跳竖直速度
+    pub fn vertical_speed(&self, charge_secs: f32) -> f32 {
+        self.min_vertical_speed + self.vertical_speed_per_charge * charge_secs
+    }
+    // 蓄力时长对应的滞空时长，height为落点相对起跳点的高度
+    pub fn flight_time(&self, charge_secs: f32, height: f32) -> f32 {
+        flight_time(self.vertical_speed(charge_secs), self.gravity, height)
+    }
+}
+
+// 以给定竖直速度起跳，下降段到达目标高度的时刻，目标过高时取最高点
+pub fn flight_time(vertical_speed: f32, gravity: f32, height: f32) -> f32 {
+    let discriminant = (vertical_speed * vertical_speed - 2.0 * gravity * height).max(0.0);
+    (vertical_speed + discriminant.sqrt()) / gravity
+}
+
+// 跳跃状态，每个跳跃者各有一份
+#[derive(Debug, Component)]
 pub struct JumpState {
     pub start_pos: Vec3,
     pub end_pos: Vec3,
-    // 跳跃动画时长，秒
-    pub animation_duration: f32,
+    // 起跳速度
+    pub velocity: Vec3,
+    pub gravity: f32,
+    // 滞空时长，秒
+    pub flight_time: f32,
+    // 已滞空时长，秒
+    pub elapsed: f32,
     pub falled: bool,
     pub completed: bool,
+    // 本跳的蓄力时长，秒
+    pub charge_secs: f32,
+    // 起跳时下一平台已移动的时长，秒
+    pub next_elapsed: f32,
+    // 本跳瞄准的候选平台序号
+    pub target: usize,
+    // 是否等待落地时判定结果
+    pub landing_pending: bool,
 }
 impl Default for JumpState {
     fn default() -> Self {
         Self {
             start_pos: Vec3::ZERO,
             end_pos: Vec3::ZERO,
-            animation_duration: 0.0,
+            velocity: Vec3::ZERO,
+            gravity: 0.0,
+            flight_time: 0.0,
+            elapsed: 0.0,
             falled: false,
             completed: true,
+            charge_secs: 0.0,
+            next_elapsed: 0.0,
+            target: 0,
+            landing_pending: false,
         }
     }
 }
 impl JumpState {
-    pub fn animate_jump(&mut self, start_pos: Vec3, end_pos: Vec3, animation_duration: f32) {
+    // 以给定竖直速度起跳，由抛体运动求出滞空时长和水平速度，使角色恰好落在end_pos
+    pub fn animate_jump(
+        &mut self,
+        start_pos: Vec3,
+        end_pos: Vec3,
+        vertical_speed: f32,
+        gravity: f32,
+    ) {
         info!("Start jump!");
+        self.set_trajectory(start_pos, end_pos, vertical_speed, gravity);
+        self.elapsed = 0.0;
+        self.completed = false;
+    }
+    // 只求出轨迹而不开始跳跃，练习模式预览轨迹时使用
+    pub fn set_trajectory(
+        &mut self,
+        start_pos: Vec3,
+        end_pos: Vec3,
+        vertical_speed: f32,
+        gravity: f32,
+    ) {
+        let flight_time = flight_time(vertical_speed, gravity, end_pos.y - start_pos.y);
+        let horizontal_velocity = (end_pos - start_pos).with_y(0.0) / flight_time;
+
         self.start_pos = start_pos;
         self.end_pos = end_pos;
-        self.animation_duration = animation_duration;
-        self.completed = false;
+        self.velocity = horizontal_velocity.with_y(vertical_speed);
+        self.gravity = gravity;
+        self.flight_time = flight_time;
+    }
+    // 起跳后t秒时角色的位置
+    pub fn position_at(&self, t: f32) -> Vec3 {
+        self.start_pos + self.velocity * t - Vec3::Y * (0.5 * self.gravity * t * t)
     }
 }
 
-// 摔落状态
-#[derive(Debug, Resource)]
+// 摔落状态，每个跳跃者各有一份
+#[derive(Debug, Component)]
 pub struct FallState {
     pub pos: Vec3,
     pub fall_type: FallType,
@@ -70,7 +239,7 @@ pub struct FallState {
 pub enum FallType {
     // 笔直下落
     Straight,
-    // 先倾斜再下落，Vec3代表倾斜方向
+    // 先倾斜再下落，Vec3为倾倒的水平方向
     Tilt(Vec3),
 }
 impl Default for FallState {
@@ -105,6 +274,24 @@ impl FallState {
 #[derive(Debug, Component)]
 pub struct Player;
 
+// 松开蓄力起跳，charge为本次蓄力时长，next_elapsed为此时下一平台已移动的时长(秒)
+// wait为蓄力前在当前平台上停留的时长(秒)，target为瞄准的候选平台序号
+#[derive(Debug, Event)]
+pub struct ChargeReleased {
+    pub charge: Duration,
+    pub next_elapsed: f32,
+    pub wait: f32,
+    pub target: usize,
+}
+
+// 落地计分所需的资源
+#[derive(SystemParam)]
+pub struct Scoring<'w> {
+    pub score: ResMut<'w, Score>,
+    pub combo: ResMut<'w, Combo>,
+    pub score_up_queue: ResMut<'w, ScoreUpQueue>,
+}
+
 #[derive(Debug, Resource)]
 pub struct GenerateAccumulationParticleEffectTimer(pub Timer);
 
@@ -112,219 +299,304 @@ pub fn setup_player(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    game_sounds: Res<GameSounds>,
+    mut sounds: EventWriter<GameSound>,
 ) {
     commands.spawn((
-        Mesh3d(meshes.add(Capsule3d::new(0.2, 0.5).mesh())),
+        Mesh3d(meshes.add(Capsule3d::new(PLAYER_RADIUS, 0.5).mesh())),
         MeshMaterial3d(materials.add(Color::Srgba(palettes::css::PINK))),
         Transform::from_translation(INITIAL_PLAYER_POS),
+        JumpState::default(),
+        FallState::default(),
         Player,
     ));
-    commands.spawn((
-        AudioPlayer(game_sounds.start.clone()),
-        PlaybackSettings::DESPAWN,
-    ));
+    sounds.send(GameSound::Start);
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 pub fn player_jump(
-    mut commands: Commands,
-    buttons: Res<ButtonInput<MouseButton>>,
-    mut score: ResMut<Score>,
+    actions: Res<ButtonInput<GameAction>>,
     mut accumulator: ResMut<Accumulator>,
-    mut jump_state: ResMut<JumpState>,
-    mut fall_state: ResMut<FallState>,
-    mut score_up_queue: ResMut<ScoreUpQueue>,
+    config: Res<GameConfig>,
+    mut run_stats: ResMut<RunStats>,
     prepare_jump_timer: Res<PrepareJumpTimer>,
-    time: Res<Time<Real>>,
-    game_sounds: Res<GameSounds>,
-    q_accumulation_sound: Query<&AudioSink, With<AccumulationSound>>,
-    q_player: Query<&Transform, With<Player>>,
-    q_current_platform: Query<(Entity, &Transform, &PlatformShape), With<CurrentPlatform>>,
-    q_next_platform: Query<(Entity, &Transform, &PlatformShape), With<NextPlatform>>,
+    mut charge_released: EventWriter<ChargeReleased>,
+    mut q_player: Query<(&Transform, &mut JumpState, &FallState), With<Player>>,
+    q_current_platform: Query<(&Transform, &PlatformShape, Option<&Dwell>), With<CurrentPlatform>>,
+    q_candidates: Query<(
+        &Transform,
+        &PlatformShape,
+        Option<&MovingPlatform>,
+        &Candidate,
+        Has<NextPlatform>,
+    )>,
 ) {
     if !prepare_jump_timer.0.finished() {
         // 防止从主菜单点击进入Playing状态时立即跳一次
         return;
     }
+    let Ok((player, mut jump_state, fall_state)) = q_player.get_single_mut() else {
+        return;
+    };
     // 如果上一跳未完成则忽略
-    if buttons.just_pressed(MouseButton::Left) && jump_state.completed && fall_state.completed {
+    if actions.just_pressed(GameAction::Charge) && jump_state.completed && fall_state.completed {
         // 开始蓄力
-        accumulator.0 = time.last_update();
-        commands.spawn((
-            AccumulationSound,
-            AudioPlayer(game_sounds.accumulation.clone()),
-            PlaybackSettings::LOOP,
-        ));
+        accumulator.0 = Some(Duration::ZERO);
     }
-    if buttons.just_released(MouseButton::Left)
+    // 暂停期间松开的蓄力在恢复后同样起跳
+    if !actions.pressed(GameAction::Charge)
         && jump_state.completed
         && fall_state.completed
         && accumulator.0.is_some()
     {
-        if q_next_platform.is_empty() {
+        let Some((_, _, next_moving, target, _)) = q_candidates.iter().find(|(.., next)| *next)
+        else {
             warn!("There is no next platform");
             return;
-        }
-        let (current_platform_entity, current_platform_transform, current_platform_shape) =
-            q_current_platform.single();
-        let (next_platform_entity, next_platform_transform, next_platform_shape) =
-            q_next_platform.single();
-        let player = q_player.single();
-
-        // 计算跳跃后的落点位置
-        let landing_pos = if (next_platform_transform.translation.x
-            - current_platform_transform.translation.x)
-            < 0.1
-        {
-            Vec3::new(
-                player.translation.x,
-                INITIAL_PLAYER_POS.y,
-                player.translation.z
-                    - 3.0 * accumulator.0.as_ref().unwrap().elapsed().as_secs_f32(),
-            )
-        } else {
-            Vec3::new(
-                player.translation.x
-                    + 3.0 * accumulator.0.as_ref().unwrap().elapsed().as_secs_f32(),
-                INITIAL_PLAYER_POS.y,
-                player.translation.z,
-            )
         };
-        dbg!(player.translation);
-        dbg!(accumulator.0.as_ref().unwrap().elapsed().as_secs_f32());
+        let (current_transform, current_shape, current_dwell) = q_current_platform.single();
+        let charge = accumulator.0.unwrap();
+        let charge_secs = charge.as_secs_f32();
+        let next_elapsed = next_moving.map_or(0.0, |moving| moving.elapsed);
+        charge_released.send(ChargeReleased {
+            charge,
+            next_elapsed,
+            wait: current_dwell.map_or(0.0, |dwell| dwell.elapsed),
+            target: target.index,
+        });
+        run_stats.jumps += 1;
+
+        // 先按候选平台位于运动中心瞄准落点，落地时再判定结果
+        let candidates =
+            course_candidates(q_candidates.iter().map(
+                |(transform, shape, moving, candidate, _)| (transform, shape, moving, candidate),
+            ));
+        let aim = resolve_jump(
+            &config.jump,
+            player.translation,
+            &course_platform(current_transform, current_shape, None),
+            &candidates,
+            target.index,
+            charge_secs,
+            0.0,
+        );
 
-        // 跳跃动画时长随距离而变化
+        // 蓄力越久起跳越高，滞空时间随之变长
         jump_state.animate_jump(
             player.translation,
-            landing_pos,
-            (accumulator.0.as_ref().unwrap().elapsed().as_secs_f32() / 2.0).max(0.5),
+            aim.landing_pos,
+            config.jump.vertical_speed(charge_secs),
+            config.jump.gravity,
         );
+        jump_state.charge_secs = charge_secs;
+        jump_state.next_elapsed = next_elapsed;
+        jump_state.target = target.index;
+        jump_state.landing_pending = true;
 
-        // 蓄力极短，跳跃后仍在当前平台上
-        // 蓄力正常，跳跃到下一平台
-        if current_platform_shape
-            .is_landed_on_platform(current_platform_transform.translation, landing_pos)
-            || next_platform_shape
-                .is_landed_on_platform(next_platform_transform.translation, landing_pos)
-        {
-            jump_state.falled = false;
-            if next_platform_shape
-                .is_landed_on_platform(next_platform_transform.translation, landing_pos)
-            {
-                // 分数加1
-                score.0 += 1;
-                score_up_queue.0.push(ScoreUpEvent {
-                    landing_pos: Vec3::new(landing_pos.x, landing_pos.y + 0.5, landing_pos.z),
-                });
+        // 结束蓄力
+        accumulator.0 = None;
+    }
+}
 
+// 落地时以候选平台此刻的位置判定落在哪里，并计分或开始摔落
+// 落到某个候选平台后其余候选平台移除
+#[allow(clippy::type_complexity)]
+pub fn player_land(
+    mut commands: Commands,
+    mut scoring: Scoring,
+    config: Res<GameConfig>,
+    mut sounds: EventWriter<GameSound>,
+    mut q_player: Query<(&mut Transform, &mut JumpState, &mut FallState), With<Player>>,
+    mut q_current_platform: Query<
+        (Entity, &Transform, &PlatformShape, Option<&mut Dwell>),
+        (With<CurrentPlatform>, Without<Player>),
+    >,
+    mut q_candidates: Query<
+        (
+            Entity,
+            &mut Transform,
+            &PlatformShape,
+            Option<&MovingPlatform>,
+            &Candidate,
+        ),
+        (Without<Player>, Without<CurrentPlatform>),
+    >,
+) {
+    let Ok((mut player, mut jump_state, mut fall_state)) = q_player.get_single_mut() else {
+        return;
+    };
+    if !jump_state.completed || !jump_state.landing_pending {
+        return;
+    }
+    jump_state.landing_pending = false;
+    let Ok(current_platform) = q_current_platform.get_single_mut() else {
+        return;
+    };
+    let (current_platform_entity, current_transform, current_shape, current_dwell) =
+        current_platform;
+    let candidates = course_candidates(
+        q_candidates
+            .iter()
+            .map(|(_, transform, shape, moving, candidate)| (transform, shape, moving, candidate)),
+    );
+    if jump_state.target >= candidates.len() {
+        return;
+    }
+    let touchdown = jump_state.next_elapsed + jump_state.flight_time;
+    let jump = resolve_jump(
+        &config.jump,
+        jump_state.start_pos,
+        &course_platform(current_transform, current_shape, None),
+        &candidates,
+        jump_state.target,
+        jump_state.charge_secs,
+        touchdown,
+    );
+    let landing_pos = jump.landing_pos;
+    player.translation = landing_pos;
+
+    match jump.outcome {
+        JumpOutcome::Stayed => {
+            jump_state.falled = false;
+            sounds.send(GameSound::Success);
+            // 原地跳一下也算起跳，停留时长重新计算
+            if let Some(mut dwell) = current_dwell {
+                dwell.elapsed = 0.0;
+            }
+        }
+        JumpOutcome::Landed { perfect } => {
+            let Some(landed) = jump.candidate else {
+                return;
+            };
+            let next = &candidates[landed];
+            jump_state.falled = false;
+            sounds.send(GameSound::Success);
+            let points = landing_points(&mut scoring.combo.0, perfect) + next.reward;
+            scoring.score.0 += points;
+            scoring.score_up_queue.0.push(ScoreUpEvent {
+                landing_pos: Vec3::new(landing_pos.x, landing_pos.y + 0.5, landing_pos.z),
+                points,
+                perfect,
+            });
+
+            for (entity, mut transform, _, _, candidate) in &mut q_candidates {
+                if candidate.index != landed {
+                    commands.entity(entity).despawn_recursive();
+                    continue;
+                }
+                // 移动平台停在落地时刻的位置
+                transform.translation = next.pos_at(touchdown);
                 commands
-                    .entity(next_platform_entity)
-                    .remove::<NextPlatform>();
-                commands
-                    .entity(next_platform_entity)
-                    .insert(CurrentPlatform);
-                commands
-                    .entity(current_platform_entity)
-                    .remove::<CurrentPlatform>();
+                    .entity(entity)
+                    .remove::<(NextPlatform, Candidate, MovingPlatform)>()
+                    .insert((CurrentPlatform, Dwell::default()));
             }
-
+            commands
+                .entity(current_platform_entity)
+                .remove::<(CurrentPlatform, Dwell)>();
+        }
         // 蓄力不足或蓄力过度，角色摔落
-        } else {
+        JumpOutcome::Fell { tilt } => {
             jump_state.falled = true;
-            if current_platform_shape.is_touched_player(
-                current_platform_transform.translation,
-                landing_pos,
-                0.2,
-            ) {
-                info!("Player touched current platform");
-                let fall_direction = if landing_pos.x == player.translation.x {
-                    Vec3::NEG_X
-                } else {
-                    Vec3::NEG_Z
-                };
-                fall_state.animate_tilt_fall(landing_pos, fall_direction);
-            } else if next_platform_shape.is_touched_player(
-                next_platform_transform.translation,
-                landing_pos,
-                0.2,
-            ) {
-                info!("Player touched next platform");
-                let fall_direction = if landing_pos.x == player.translation.x {
-                    if landing_pos.z < next_platform_transform.translation.z {
-                        Vec3::NEG_X
-                    } else {
-                        Vec3::X
-                    }
-                } else {
-                    if landing_pos.x < next_platform_transform.translation.x {
-                        Vec3::Z
-                    } else {
-                        Vec3::NEG_Z
-                    }
-                };
-                fall_state.animate_tilt_fall(landing_pos, fall_direction);
-            } else {
-                fall_state.animate_straight_fall(landing_pos);
+            match tilt {
+                Some(fall_direction) => fall_state.animate_tilt_fall(landing_pos, fall_direction),
+                None => fall_state.animate_straight_fall(landing_pos),
             }
         }
-
-        // 结束蓄力
-        accumulator.0 = None;
-        for sink in q_accumulation_sound.iter() {
-            sink.pause();
-        }
     }
 }
 
-pub fn animate_jump(
+// 有多个候选平台时，蓄力前用左右方向键按屏幕上的左右顺序切换瞄准的平台
+// 在落地判定之后运行，以免瞄准刚落上去的平台
+#[allow(clippy::type_complexity)]
+pub fn aim_next_platform(
     mut commands: Commands,
-    mut jump_state: ResMut<JumpState>,
-    time: Res<Time>,
-    mut q_player: Query<&mut Transform, With<Player>>,
-    game_sounds: Res<GameSounds>,
+    actions: Res<ButtonInput<GameAction>>,
+    accumulator: Res<Accumulator>,
+    q_player: Query<(&Transform, &JumpState, &FallState), With<Player>>,
+    q_candidates: Query<(
+        Entity,
+        &Transform,
+        Option<&MovingPlatform>,
+        &Candidate,
+        Has<NextPlatform>,
+    )>,
 ) {
-    if !jump_state.completed {
-        let mut player = q_player.single_mut();
-
-        // TODO 围绕中心点圆周?运动
-        let around_point = Vec3::new(
-            (jump_state.start_pos.x + jump_state.end_pos.x) / 2.0,
-            (jump_state.start_pos.y + jump_state.end_pos.y) / 2.0,
-            (jump_state.start_pos.z + jump_state.end_pos.z) / 2.0,
-        );
+    let step: isize = if actions.just_pressed(GameAction::AimRight) {
+        1
+    } else if actions.just_pressed(GameAction::AimLeft) {
+        -1
+    } else {
+        return;
+    };
+    let Ok((player, jump_state, fall_state)) = q_player.get_single() else {
+        return;
+    };
+    if accumulator.0.is_some() || !jump_state.completed || !fall_state.completed {
+        return;
+    }
+    // 相机沿FORWARD看向角色，FORWARD.perp()指向屏幕右方
+    let right = FORWARD.perp();
+    let mut candidates: Vec<_> = q_candidates
+        .iter()
+        .map(|(_, transform, moving, candidate, next)| {
+            let pos = moving.map_or(transform.translation, |moving| moving.origin);
+            (
+                (pos - player.translation).xz().dot(right),
+                candidate.index,
+                next,
+            )
+        })
+        .collect();
+    candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let Some(aimed) = candidates.iter().position(|(_, _, next)| *next) else {
+        return;
+    };
+    let Some((_, index, _)) = aimed
+        .checked_add_signed(step)
+        .and_then(|aimed| candidates.get(aimed))
+    else {
+        return;
+    };
+    aim_candidate(
+        &mut commands,
+        q_candidates
+            .iter()
+            .map(|(entity, _, _, candidate, _)| (entity, candidate)),
+        *index,
+    );
+}
 
-        let rotate_axis = if (jump_state.end_pos.x - jump_state.start_pos.x) < 0.1 {
-            Vec3::X
-        } else {
-            Vec3::Z
-        };
-        let quat = Quat::from_axis_angle(
-            rotate_axis,
-            -(1.0 / jump_state.animation_duration) * PI * time.delta_secs(),
-        );
+// 累计蓄力时长，暂停时该系统不运行
+pub fn tick_accumulator(mut accumulator: ResMut<Accumulator>, time: Res<Time>) {
+    if let Some(charge) = accumulator.0.as_mut() {
+        *charge += time.delta();
+    }
+}
 
-        let mut clone_player = player.clone();
-        clone_player.translate_around(around_point, quat);
-        if clone_player.translation.y < INITIAL_PLAYER_POS.y {
+// 驱动所有跳跃者的跳跃动画
+pub fn animate_jump(time: Res<Time>, mut q_jumper: Query<(&mut Transform, &mut JumpState)>) {
+    for (mut player, mut jump_state) in &mut q_jumper {
+        if jump_state.completed {
+            continue;
+        }
+        jump_state.elapsed += time.delta_secs();
+        if jump_state.elapsed >= jump_state.flight_time {
             player.translation = jump_state.end_pos;
             player.rotation = Quat::IDENTITY;
 
             // 结束跳跃
             jump_state.completed = true;
-            if !jump_state.falled {
-                commands.spawn((
-                    AudioPlayer(game_sounds.success.clone()),
-                    PlaybackSettings::DESPAWN,
-                ));
-            }
         } else {
-            player.translate_around(around_point, quat);
-
-            // 自身旋转
-            player.rotate_local_axis(
-                Dir3::new_unchecked(rotate_axis),
-                -(1.0 / jump_state.animation_duration) * TAU * time.delta_secs(),
-            );
+            player.translation = jump_state.position_at(jump_state.elapsed);
+
+            // 沿跳跃方向向前翻转一周，与滞空时长同步以保证落地时直立
+            let direction = (jump_state.end_pos - jump_state.start_pos).with_y(0.0);
+            if let Some(rotate_axis) = Vec3::Y.cross(direction).try_normalize() {
+                player.rotation = Quat::from_axis_angle(
+                    rotate_axis,
+                    TAU * jump_state.elapsed / jump_state.flight_time,
+                );
+            }
         }
     }
 }
@@ -335,13 +607,16 @@ pub fn animate_player_accumulation(
     accumulator: Res<Accumulator>,
     mut q_player: Query<&mut Transform, With<Player>>,
     time: Res<Time>,
+    config: Res<GameConfig>,
 ) {
     let mut player = q_player.single_mut();
     match accumulator.0 {
         Some(_) => {
-            player.scale.x = (player.scale.x + 0.12 * time.delta_secs()).min(1.3);
-            player.scale.y = (player.scale.y - 0.15 * time.delta_secs()).max(0.6);
-            player.scale.z = (player.scale.z + 0.12 * time.delta_secs()).min(1.3);
+            let stretch = config.player_stretch_rate * time.delta_secs();
+            let squash = config.player_squash_rate * time.delta_secs();
+            player.scale.x = (player.scale.x + stretch).min(1.3);
+            player.scale.y = (player.scale.y - squash).max(0.6);
+            player.scale.z = (player.scale.z + stretch).min(1.3);
         }
         None => {
             player.scale = Vec3::ONE;
@@ -349,33 +624,33 @@ pub fn animate_player_accumulation(
     }
 }
 
+// 驱动所有跳跃者的摔落动画，只有玩家摔落在地时结束游戏
 pub fn animate_fall(
-    mut commands: Commands,
-    mut fall_state: ResMut<FallState>,
-    jump_state: Res<JumpState>,
     time: Res<Time>,
     mut next_game_state: ResMut<NextState<GameState>>,
-    mut q_player: Query<&mut Transform, With<Player>>,
-    game_sounds: Res<GameSounds>,
+    mut q_jumper: Query<(&mut Transform, &mut FallState, &JumpState, Has<Player>)>,
+    mut sounds: EventWriter<GameSound>,
+    config: Res<GameConfig>,
 ) {
-    if !fall_state.completed && jump_state.completed {
-        if !fall_state.played_sound {
-            commands.spawn((
-                AudioPlayer(game_sounds.fall.clone()),
-                PlaybackSettings::DESPAWN,
-            ));
+    for (mut player, mut fall_state, jump_state, is_player) in &mut q_jumper {
+        if fall_state.completed || !jump_state.completed {
+            continue;
+        }
+        if is_player && !fall_state.played_sound {
+            sounds.send(GameSound::Fall);
             fall_state.played_sound = true;
         }
-        let mut player = q_player.single_mut();
         match fall_state.fall_type {
             FallType::Straight => {
                 if player.translation.y < 0.5 {
                     // 已摔落在地
                     fall_state.completed = true;
-                    info!("Game over!");
-                    next_game_state.set(GameState::GameOver);
+                    if is_player {
+                        info!("Game over!");
+                        next_game_state.set(GameState::GameOver);
+                    }
                 } else {
-                    player.translation.y -= 0.7 * time.delta_secs();
+                    player.translation.y -= config.fall_speed * time.delta_secs();
                 }
             }
             FallType::Tilt(direction) => {
@@ -383,14 +658,16 @@ pub fn animate_fall(
                     // 倾斜
                     let around_point = Vec3::new(
                         fall_state.pos.x,
-                        INITIAL_PLAYER_POS.y - 0.5,
+                        fall_state.pos.y - PLAYER_HALF_HEIGHT,
                         fall_state.pos.z,
                     );
                     if player.translation.y < around_point.y {
                         fall_state.tilt_completed = true;
                     } else {
+                        // 头部转向倾倒方向
+                        let axis = Vec3::Y.cross(direction).normalize_or(Vec3::X);
                         let quat =
-                            Quat::from_axis_angle(direction, 1.0 * FRAC_PI_2 * time.delta_secs());
+                            Quat::from_axis_angle(axis, config.tilt_speed * time.delta_secs());
                         player.rotate_around(around_point, quat);
                     }
                 } else {
@@ -398,10 +675,12 @@ pub fn animate_fall(
                     if player.translation.y < 0.2 {
                         // 已摔落在地
                         fall_state.completed = true;
-                        info!("Game over!");
-                        next_game_state.set(GameState::GameOver);
+                        if is_player {
+                            info!("Game over!");
+                            next_game_state.set(GameState::GameOver);
+                        }
                     } else {
-                        player.translation.y -= 0.7 * time.delta_secs();
+                        player.translation.y -= config.fall_speed * time.delta_secs();
                     }
                 }
             }
@@ -507,6 +786,71 @@ pub fn prepare_jump(time: Res<Time>, mut prepare_timer: ResMut<PrepareJumpTimer>
     prepare_timer.0.tick(time.delta());
 }
 
-pub fn reset_prepare_jump_timer(mut prepare_timer: ResMut<PrepareJumpTimer>) {
-    prepare_timer.0.reset();
+pub fn reset_prepare_jump_timer(
+    mut prepare_timer: ResMut<PrepareJumpTimer>,
+    config: Res<GameConfig>,
+) {
+    prepare_timer.0 = Timer::from_seconds(config.prepare_jump_secs, TimerMode::Once);
+}
+
+// 暂停时未松开的蓄力不带入新的一局，否则开局后会自动起跳
+pub fn reset_accumulator(
+    mut commands: Commands,
+    mut accumulator: ResMut<Accumulator>,
+    q_accumulation_sound: Query<Entity, With<AccumulationSound>>,
+) {
+    accumulator.0 = None;
+    for sound in &q_accumulation_sound {
+        commands.entity(sound).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jump_lands_exactly_on_end_pos() {
+        let physics = JumpPhysics::default();
+        for (end_pos, charge_secs) in [
+            (Vec3::new(3.0, 1.5, 0.0), 1.0),
+            (Vec3::new(0.0, 2.0, -2.5), 0.8),
+            (Vec3::new(4.0, 1.0, 0.0), 1.3),
+        ] {
+            let mut jump_state = JumpState::default();
+            jump_state.animate_jump(
+                INITIAL_PLAYER_POS,
+                end_pos,
+                physics.vertical_speed(charge_secs),
+                physics.gravity,
+            );
+            let landed = jump_state.position_at(jump_state.flight_time);
+            assert!(landed.distance(end_pos) < 1e-4, "{landed} != {end_pos}");
+        }
+    }
+
+    #[test]
+    fn flight_time_grows_with_charge() {
+        let physics = JumpPhysics::default();
+        let mut short_jump = JumpState::default();
+        short_jump.animate_jump(
+            INITIAL_PLAYER_POS,
+            INITIAL_PLAYER_POS + Vec3::X,
+            physics.vertical_speed(0.3),
+            physics.gravity,
+        );
+        let mut long_jump = JumpState::default();
+        long_jump.animate_jump(
+            INITIAL_PLAYER_POS,
+            INITIAL_PLAYER_POS + 4.0 * Vec3::X,
+            physics.vertical_speed(1.3),
+            physics.gravity,
+        );
+        assert!(short_jump.flight_time < long_jump.flight_time);
+        // 同高度落地时滞空时长为2v/g
+        assert!(
+            (short_jump.flight_time - 2.0 * physics.vertical_speed(0.3) / physics.gravity).abs()
+                < 1e-5
+        );
+    }
 }
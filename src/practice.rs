@@ -0,0 +1,189 @@
+use bevy::prelude::*;
+use std::f32::consts::FRAC_PI_2;
+
+use crate::config::GameConfig;
+use crate::platform::{
+    course_candidates, course_platform, Candidate, CurrentPlatform, MovingPlatform, NextPlatform,
+    PlatformShape,
+};
+use crate::player::{player_jump, Accumulator, JumpState, Player, PLAYER_HALF_HEIGHT};
+use crate::sim::{resolve_jump, JumpOutcome};
+use crate::ui::{GameMode, GameState, NewGameSet};
+
+// 练习模式：蓄力时在地面上标出此刻松开的落点，可选显示跳跃轨迹，成绩不计入存档
+pub struct PracticePlugin;
+
+impl Plugin for PracticePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::MainMenu), clear_aim_preview)
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    clear_aim_preview,
+                    setup_aim_preview.after(clear_aim_preview),
+                )
+                    .in_set(NewGameSet),
+            )
+            .add_systems(
+                Update,
+                update_aim_preview
+                    .after(player_jump)
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(resource_equals(GameMode::Practice)),
+            );
+    }
+}
+
+// 轨迹上的点数
+pub const TRAJECTORY_DOTS: usize = 16;
+
+// 预计落点的标记，颜色表示落地结果
+#[derive(Debug, Component)]
+pub struct AimReticle;
+
+// 轨迹上的第几个点
+#[derive(Debug, Component)]
+pub struct TrajectoryDot(pub usize);
+
+pub fn setup_aim_preview(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    game_mode: Res<GameMode>,
+) {
+    if *game_mode != GameMode::Practice {
+        return;
+    }
+    commands.spawn((
+        Mesh3d(meshes.add(Annulus::new(0.12, 0.2))),
+        MeshMaterial3d(materials.add(StandardMaterial {
+            base_color: Color::WHITE,
+            unlit: true,
+            ..default()
+        })),
+        Transform::from_rotation(Quat::from_rotation_x(-FRAC_PI_2)),
+        Visibility::Hidden,
+        AimReticle,
+    ));
+    let dot_mesh = meshes.add(Sphere::new(0.04));
+    let dot_material = materials.add(StandardMaterial {
+        base_color: Color::srgba(1.0, 1.0, 1.0, 0.7),
+        alpha_mode: AlphaMode::Blend,
+        unlit: true,
+        ..default()
+    });
+    for index in 0..TRAJECTORY_DOTS {
+        commands.spawn((
+            Mesh3d(dot_mesh.clone()),
+            MeshMaterial3d(dot_material.clone()),
+            Transform::default(),
+            Visibility::Hidden,
+            TrajectoryDot(index),
+        ));
+    }
+}
+
+// 蓄力期间按当前蓄力时长预测落点，与player_jump起跳时的计算相同，移动平台按此刻松开时的落地时刻判定
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
+pub fn update_aim_preview(
+    accumulator: Res<Accumulator>,
+    config: Res<GameConfig>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    q_player: Query<&Transform, With<Player>>,
+    q_current_platform: Query<(&Transform, &PlatformShape), With<CurrentPlatform>>,
+    q_candidates: Query<(
+        &Transform,
+        &PlatformShape,
+        Option<&MovingPlatform>,
+        &Candidate,
+        Has<NextPlatform>,
+    )>,
+    mut q_reticle: Query<
+        (
+            &mut Transform,
+            &mut Visibility,
+            &MeshMaterial3d<StandardMaterial>,
+        ),
+        (With<AimReticle>, Without<Player>, Without<PlatformShape>),
+    >,
+    mut q_dots: Query<
+        (&mut Transform, &mut Visibility, &TrajectoryDot),
+        (Without<AimReticle>, Without<Player>, Without<PlatformShape>),
+    >,
+) {
+    let preview = accumulator.0.and_then(|charge| {
+        let player = q_player.get_single().ok()?;
+        let (current_transform, current_shape) = q_current_platform.get_single().ok()?;
+        let (_, _, next_moving, target, _) = q_candidates.iter().find(|(.., next)| *next)?;
+        let candidates =
+            course_candidates(q_candidates.iter().map(
+                |(transform, shape, moving, candidate, _)| (transform, shape, moving, candidate),
+            ));
+        let current = course_platform(current_transform, current_shape, None);
+        let charge_secs = charge.as_secs_f32();
+        let resolve = |next_elapsed| {
+            resolve_jump(
+                &config.jump,
+                player.translation,
+                &current,
+                &candidates,
+                target.index,
+                charge_secs,
+                next_elapsed,
+            )
+        };
+        let aim = resolve(0.0);
+        let mut arc = JumpState::default();
+        arc.set_trajectory(
+            player.translation,
+            aim.landing_pos,
+            config.jump.vertical_speed(charge_secs),
+            config.jump.gravity,
+        );
+        let release = next_moving.map_or(0.0, |moving| moving.elapsed);
+        let jump = resolve(release + arc.flight_time);
+        Some((arc, jump))
+    });
+
+    for (mut transform, mut visibility, material) in &mut q_reticle {
+        let Some((_, jump)) = &preview else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        // 直接摔落时标在地面上，否则标在落点所在平台的顶面
+        let y = match jump.outcome {
+            JumpOutcome::Fell { tilt: None } => 0.01,
+            _ => jump.landing_pos.y - PLAYER_HALF_HEIGHT + 0.01,
+        };
+        let color = match jump.outcome {
+            JumpOutcome::Fell { .. } => Color::srgb(1.0, 0.3, 0.3),
+            JumpOutcome::Landed { perfect: true } => Color::srgb(1.0, 0.8, 0.2),
+            JumpOutcome::Stayed | JumpOutcome::Landed { perfect: false } => Color::WHITE,
+        };
+        transform.translation = jump.landing_pos.with_y(y);
+        if let Some(material) = materials.get_mut(material) {
+            material.base_color = color;
+        }
+    }
+
+    for (mut transform, mut visibility, dot) in &mut q_dots {
+        let Some((arc, _)) = preview.as_ref().filter(|_| config.practice_trajectory) else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+        *visibility = Visibility::Inherited;
+        let t = arc.flight_time * (dot.0 + 1) as f32 / (TRAJECTORY_DOTS + 1) as f32;
+        transform.translation = arc.position_at(t);
+    }
+}
+
+#[allow(clippy::type_complexity)]
+pub fn clear_aim_preview(
+    mut commands: Commands,
+    q_preview: Query<Entity, Or<(With<AimReticle>, With<TrajectoryDot>)>>,
+) {
+    for entity in &q_preview {
+        commands.entity(entity).despawn();
+    }
+}
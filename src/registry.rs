@@ -0,0 +1,468 @@
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext, LoadState};
+use bevy::prelude::*;
+use rand::distributions::WeightedIndex;
+use rand::prelude::Distribution;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::platform::PlatformLimits;
+
+// 平台类型表，每种平台的轮廓、外观、出现权重和特殊行为都由数据文件声明
+// 从assets/config/default.platforms.ron加载，新增平台类型不需要修改代码
+
+// 平台俯视轮廓，按尺寸为1设计，生成时按随机尺寸缩放
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Footprint {
+    // 边长为1的正方形
+    Square,
+    // 直径为1的圆
+    Circle,
+    // 简单多边形，顶点坐标为(x, z)，应位于[-0.5, 0.5]内且包含中心
+    Polygon { points: Vec<Vec2> },
+}
+
+impl Footprint {
+    // 缩放后的点offset是否在轮廓内
+    pub fn contains(&self, size: f32, offset: Vec2) -> bool {
+        let half = size / 2.0;
+        match self {
+            Self::Square => offset.x.abs() < half && offset.y.abs() < half,
+            Self::Circle => offset.length() < half,
+            Self::Polygon { points } => polygon_contains(points, offset / size),
+        }
+    }
+    // 缩放后的点offset到轮廓的距离，在轮廓内为0
+    pub fn distance(&self, size: f32, offset: Vec2) -> f32 {
+        let half = size / 2.0;
+        match self {
+            Self::Square => {
+                let closest = offset.clamp(Vec2::splat(-half), Vec2::splat(half));
+                offset.distance(closest)
+            }
+            Self::Circle => (offset.length() - half).max(0.0),
+            Self::Polygon { points } => {
+                if polygon_contains(points, offset / size) {
+                    return 0.0;
+                }
+                edges(points)
+                    .map(|(a, b)| segment_distance(offset, a * size, b * size))
+                    .fold(f32::INFINITY, f32::min)
+            }
+        }
+    }
+    // 点offset距中心的距离，方形按切比雪夫距离计算
+    pub fn center_distance(&self, offset: Vec2) -> f32 {
+        match self {
+            Self::Square => offset.abs().max_element(),
+            Self::Circle | Self::Polygon { .. } => offset.length(),
+        }
+    }
+    // 外接圆半径，按尺寸1计算
+    pub fn radius(&self) -> f32 {
+        match self {
+            Self::Square => std::f32::consts::FRAC_1_SQRT_2,
+            Self::Circle => 0.5,
+            Self::Polygon { points } => points.iter().map(|p| p.length()).fold(0.0, f32::max),
+        }
+    }
+}
+
+fn edges(points: &[Vec2]) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+    points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(a, b)| (*a, *b))
+}
+
+// 射线法判断点是否在多边形内
+fn polygon_contains(points: &[Vec2], p: Vec2) -> bool {
+    edges(points).fold(false, |inside, (a, b)| {
+        let crosses =
+            (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x);
+        inside != crosses
+    })
+}
+
+fn segment_distance(p: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let ab = b - a;
+    let t = ((p - a).dot(ab) / ab.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+    p.distance(a + ab * t)
+}
+
+// 耳切法三角化简单多边形，返回各三角形的顶点序号
+pub fn triangulate(points: &[Vec2]) -> Vec<[usize; 3]> {
+    let area: f32 = edges(points).map(|(a, b)| a.perp_dot(b)).sum();
+    let ccw = area > 0.0;
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::new();
+    while remaining.len() > 3 {
+        let n = remaining.len();
+        let corner = |i: usize| {
+            [
+                remaining[(i + n - 1) % n],
+                remaining[i],
+                remaining[(i + 1) % n],
+            ]
+        };
+        let ear = (0..n).find(|&i| {
+            let [ia, ib, ic] = corner(i);
+            let (a, b, c) = (points[ia], points[ib], points[ic]);
+            let turn = (b - a).perp_dot(c - b);
+            let convex = if ccw { turn > 0.0 } else { turn < 0.0 };
+            convex
+                && !remaining
+                    .iter()
+                    .filter(|&&j| j != ia && j != ib && j != ic)
+                    .any(|&j| in_triangle(points[j], a, b, c))
+        });
+        // 退化的多边形，放弃剩余部分
+        let Some(i) = ear else {
+            break;
+        };
+        triangles.push(corner(i));
+        remaining.remove(i);
+    }
+    if remaining.len() == 3 {
+        triangles.push([remaining[0], remaining[1], remaining[2]]);
+    }
+    triangles
+}
+
+fn in_triangle(p: Vec2, a: Vec2, b: Vec2, c: Vec2) -> bool {
+    let d1 = (b - a).perp_dot(p - a);
+    let d2 = (c - b).perp_dot(p - b);
+    let d3 = (a - c).perp_dot(p - c);
+    let negative = d1 < 0.0 || d2 < 0.0 || d3 < 0.0;
+    let positive = d1 > 0.0 || d2 > 0.0 || d3 > 0.0;
+    !(negative && positive)
+}
+
+// 一种平台类型
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PlatformType {
+    pub name: String,
+    pub footprint: Footprint,
+    // glTF场景路径，按尺寸1、高度1制作，缺省时按轮廓拉伸生成网格
+    #[serde(default)]
+    pub scene: Option<String>,
+    // 固定颜色(srgb)，缺省时随机
+    #[serde(default)]
+    pub color: Option<(f32, f32, f32)>,
+    // 出现权重
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    // 分数达到该值后才会出现
+    #[serde(default)]
+    pub min_score: u32,
+    // 在上面停留一段时间不起跳获得的加分
+    #[serde(default)]
+    pub bonus: Option<u32>,
+    // 是否可以作为移动平台
+    #[serde(default = "default_moving")]
+    pub moving: bool,
+    // 顶面的装饰件，使特殊平台与同色的普通平台区分开
+    #[serde(default)]
+    pub decorations: Vec<Decoration>,
+}
+
+// 平台顶面的装饰件，按尺寸1设计，offset以顶面中心为原点
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Decoration {
+    pub shape: DecorationShape,
+    #[serde(default)]
+    pub offset: Vec3,
+    // 颜色(srgb)
+    pub color: (f32, f32, f32),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecorationShape {
+    // 长方体，size为(x, y, z)三个方向的边长
+    Cuboid { size: Vec3 },
+    // 竖直的圆柱
+    Cylinder { radius: f32, height: f32 },
+    // 平放的圆环，radius为环中心线的半径
+    Ring { radius: f32, thickness: f32 },
+}
+
+impl DecorationShape {
+    pub fn mesh(&self) -> Mesh {
+        match *self {
+            Self::Cuboid { size } => Mesh::from(Cuboid::from_size(size)),
+            Self::Cylinder { radius, height } => Mesh::from(Cylinder::new(radius, height)),
+            Self::Ring { radius, thickness } => Mesh::from(Torus {
+                minor_radius: thickness / 2.0,
+                major_radius: radius,
+            }),
+        }
+    }
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+fn default_moving() -> bool {
+    true
+}
+
+impl PlatformType {
+    // 检查权重为正，多边形轮廓位于尺寸1的范围内且包含中心
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.weight.is_finite() || self.weight <= 0.0 {
+            return Err(format!("weight ({}) must be positive", self.weight));
+        }
+        if let Footprint::Polygon { points } = &self.footprint {
+            if points.len() < 3 {
+                return Err("polygon needs at least 3 points".to_string());
+            }
+            if let Some(point) = points
+                .iter()
+                .find(|point| !point.is_finite() || point.abs().max_element() > 0.5)
+            {
+                return Err(format!("polygon point {point} is outside [-0.5, 0.5]"));
+            }
+        }
+        if !self.footprint.contains(1.0, Vec2::ZERO) {
+            return Err("footprint does not contain the center".to_string());
+        }
+        Ok(())
+    }
+    pub fn new(name: &str, footprint: Footprint) -> Self {
+        Self {
+            name: name.to_string(),
+            footprint,
+            scene: None,
+            color: None,
+            weight: default_weight(),
+            min_score: 0,
+            bonus: None,
+            moving: default_moving(),
+            decorations: Vec::new(),
+        }
+    }
+}
+
+// 平台类型表，数据文件缺失或无效时只有方形和圆柱两种平台
+#[derive(Debug, Clone, PartialEq, Deserialize, Asset, TypePath)]
+pub struct PlatformRegistry {
+    pub types: Vec<PlatformType>,
+}
+
+impl Default for PlatformRegistry {
+    fn default() -> Self {
+        Self {
+            types: vec![
+                PlatformType::new("box", Footprint::Square),
+                PlatformType::new("cylinder", Footprint::Circle),
+            ],
+        }
+    }
+}
+
+impl PlatformRegistry {
+    pub fn get(&self, name: &str) -> Option<&PlatformType> {
+        self.types.iter().find(|platform| platform.name == name)
+    }
+    // 解析RON格式的类型表，有无效类型时整个类型表无效
+    pub fn from_ron(bytes: &[u8]) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let registry: Self = ron::de::from_bytes(bytes)?;
+        if registry.types.is_empty() {
+            return Err("platform registry has no types".into());
+        }
+        for platform in &registry.types {
+            platform
+                .validate()
+                .map_err(|err| format!("platform type {}: {err}", platform.name))?;
+        }
+        Ok(registry)
+    }
+    // 按权重随机选择分数达到要求的平台类型，权重按难度参数中的倍数调整
+    pub fn pick(&self, rng: &mut ChaCha8Rng, score: u32, limits: &PlatformLimits) -> &PlatformType {
+        let weights = self.types.iter().map(|platform| {
+            if platform.min_score <= score {
+                (platform.weight * limits.weight_scale(&platform.name)).max(0.0)
+            } else {
+                0.0
+            }
+        });
+        // 权重全为0或还没有解锁的类型时使用第一种
+        match WeightedIndex::new(weights) {
+            Ok(weights) => &self.types[weights.sample(rng)],
+            Err(_) => &self.types[0],
+        }
+    }
+}
+
+#[derive(Debug, Resource)]
+pub struct PlatformRegistryHandle(pub Handle<PlatformRegistry>);
+
+#[derive(Default)]
+pub struct PlatformRegistryLoader;
+
+impl AssetLoader for PlatformRegistryLoader {
+    type Asset = PlatformRegistry;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        // 加载失败时保留之前的类型表
+        PlatformRegistry::from_ron(&bytes).inspect_err(|err| {
+            warn!("Invalid platform registry, keeping the previous one: {err}");
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["platforms.ron"]
+    }
+}
+
+pub fn load_platform_registry(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(PlatformRegistryHandle(
+        asset_server.load("config/default.platforms.ron"),
+    ));
+}
+
+// 与难度曲线相同，类型表加载完成或失败后才生成平台
+pub fn platform_registry_ready(
+    asset_server: Res<AssetServer>,
+    registries: Res<Assets<PlatformRegistry>>,
+    registry_handle: Res<PlatformRegistryHandle>,
+) -> bool {
+    registries.contains(&registry_handle.0)
+        || matches!(
+            asset_server.load_state(&registry_handle.0),
+            LoadState::Failed(_)
+        )
+}
+
+// 当前使用的平台类型表
+pub fn current_registry(
+    registries: &Assets<PlatformRegistry>,
+    registry_handle: &PlatformRegistryHandle,
+) -> PlatformRegistry {
+    registries
+        .get(&registry_handle.0)
+        .cloned()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    fn l_block() -> Footprint {
+        Footprint::Polygon {
+            points: vec![
+                Vec2::new(-0.5, -0.5),
+                Vec2::new(0.5, -0.5),
+                Vec2::new(0.5, 0.1),
+                Vec2::new(0.1, 0.1),
+                Vec2::new(0.1, 0.5),
+                Vec2::new(-0.5, 0.5),
+            ],
+        }
+    }
+
+    #[test]
+    fn polygon_footprint_handles_concave_corner() {
+        let footprint = l_block();
+        assert!(footprint.contains(2.0, Vec2::ZERO));
+        assert!(footprint.contains(2.0, Vec2::new(-0.8, 0.8)));
+        // L形缺口处不在平台上
+        assert!(!footprint.contains(2.0, Vec2::new(0.6, 0.6)));
+        assert!((footprint.distance(2.0, Vec2::new(0.6, 0.3)) - 0.1).abs() < 1e-5);
+        assert_eq!(footprint.distance(2.0, Vec2::ZERO), 0.0);
+        let Footprint::Polygon { points } = footprint else {
+            unreachable!();
+        };
+        assert_eq!(triangulate(&points).len(), 4);
+    }
+
+    #[test]
+    fn pick_respects_min_score() {
+        let registry = PlatformRegistry {
+            types: vec![
+                PlatformType::new("box", Footprint::Square),
+                PlatformType {
+                    min_score: 10,
+                    ..PlatformType::new("star", l_block())
+                },
+            ],
+        };
+        let limits = PlatformLimits::default();
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        assert!((0..50).all(|_| registry.pick(&mut rng, 0, &limits).name == "box"));
+        assert!((0..50).any(|_| registry.pick(&mut rng, 10, &limits).name == "star"));
+    }
+
+    #[test]
+    fn pick_applies_weight_scales() {
+        let registry = PlatformRegistry::default();
+        let mut limits = PlatformLimits::default();
+        limits.weight_scales.insert("box".to_string(), 0.0);
+        let mut rng = ChaCha8Rng::seed_from_u64(1);
+        assert!((0..50).all(|_| registry.pick(&mut rng, 0, &limits).name == "cylinder"));
+        // 没有可选的类型时退回第一种而不是panic
+        limits.weight_scales.insert("cylinder".to_string(), 0.0);
+        assert_eq!(registry.pick(&mut rng, 0, &limits).name, "box");
+    }
+
+    #[test]
+    fn invalid_types_are_rejected() {
+        let zero_weight = b"(types: [(name: \"box\", footprint: square, weight: 0.0)])";
+        assert!(PlatformRegistry::from_ron(zero_weight).is_err());
+        let oversized = b"(types: [(name: \"big\", footprint: polygon(points: [(-1.0, -1.0), (1.0, -1.0), (0.0, 1.0)]))])";
+        let err = PlatformRegistry::from_ron(oversized).unwrap_err();
+        assert!(err.to_string().contains("big"));
+        assert!(PlatformRegistry::from_ron(b"(types: [])").is_err());
+        assert!(
+            PlatformRegistry::from_ron(b"(types: [(name: \"box\", footprint: square)])").is_ok()
+        );
+    }
+
+    #[test]
+    fn default_registry_asset_parses() {
+        let path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/assets/config/default.platforms.ron"
+        );
+        let registry = PlatformRegistry::from_ron(&std::fs::read(path).unwrap()).unwrap();
+        assert!(registry.get("box").is_some());
+        for platform in &registry.types {
+            // 完美落点必须在平台上
+            assert!(
+                platform.footprint.contains(1.0, Vec2::ZERO),
+                "{}",
+                platform.name
+            );
+            // 特殊平台必须能与普通平台区分
+            if platform.bonus.is_some() {
+                assert!(
+                    platform.scene.is_some() || !platform.decorations.is_empty(),
+                    "{}",
+                    platform.name
+                );
+            }
+            if let Footprint::Polygon { points } = &platform.footprint {
+                assert_eq!(
+                    triangulate(points).len(),
+                    points.len() - 2,
+                    "{}",
+                    platform.name
+                );
+            }
+        }
+    }
+}
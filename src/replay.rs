@@ -0,0 +1,330 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::input::GameAction;
+use crate::platform::{
+    aim_candidate, move_platforms, reset_platform_rng, Candidate, CurrentPlatform, Dwell, GameSeed,
+    MovingPlatform,
+};
+use crate::player::{
+    player_jump, tick_accumulator, Accumulator, ChargeReleased, FallState, JumpState, Player,
+    PrepareJumpTimer,
+};
+use crate::save::{record_run, RunStats, SaveData};
+use crate::ui::{despawn_screen, GameMode, GameState, NewGameSet};
+
+// 录像格式版本，格式不兼容时递增
+pub const REPLAY_VERSION: u32 = 4;
+// 可选的回放速度
+pub const REPLAY_SPEEDS: [f32; 4] = [0.5, 1.0, 2.0, 4.0];
+const NORMAL_SPEED: usize = 1;
+
+// 录像的录制、保存和回放
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ChargeReleased>()
+            .init_resource::<ReplayRecorder>()
+            .init_resource::<ReplayPlayback>()
+            .add_systems(
+                OnEnter(GameState::MainMenu),
+                (reset_replay_speed, despawn_screen::<ReplayHud>),
+            )
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    start_recording.after(reset_platform_rng),
+                    despawn_screen::<ReplayHud>,
+                    setup_replay_hud.after(despawn_screen::<ReplayHud>),
+                )
+                    .in_set(NewGameSet),
+            )
+            .add_systems(
+                Update,
+                (
+                    record_jumps.after(player_jump),
+                    (
+                        drive_replay
+                            .after(tick_accumulator)
+                            .after(move_platforms)
+                            .before(player_jump),
+                        change_replay_speed,
+                    )
+                        .run_if(resource_equals(GameMode::Replay)),
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                (reset_replay_speed, save_replay.after(record_run)),
+            );
+    }
+}
+
+// 一局游戏的录像，平台序列由种子决定，只需记录每一跳的蓄力时长、蓄力前的停留时长、起跳时刻和瞄准的平台
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    // 每一跳的蓄力时长，纳秒，保证回放与录制时完全一致
+    pub charges: Vec<u64>,
+    // 每一跳起跳时下一平台已移动的时长，秒，静止平台为0
+    pub releases: Vec<f32>,
+    // 每一跳蓄力前在当前平台上停留的时长，秒
+    pub waits: Vec<f32>,
+    // 每一跳瞄准的候选平台序号，0为常规平台
+    pub targets: Vec<u32>,
+}
+
+impl Replay {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            seed,
+            charges: Vec::new(),
+            releases: Vec::new(),
+            waits: Vec::new(),
+            targets: Vec::new(),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let replay: Self = ron::from_str(&std::fs::read_to_string(path)?)?;
+        if replay.version != REPLAY_VERSION {
+            return Err(format!("unsupported replay version {}", replay.version).into());
+        }
+        Ok(replay)
+    }
+
+    pub fn store(&self, path: &Path) -> std::io::Result<()> {
+        let content = ron::to_string(self).map_err(std::io::Error::other)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, content)
+    }
+
+    // 存档旁的录像文件，内存存档时没有
+    pub fn path(save_data: &SaveData, name: &str) -> Option<PathBuf> {
+        let dir = save_data.path.as_ref()?.parent()?;
+        Some(dir.join("replays").join(format!("{name}.replay.ron")))
+    }
+}
+
+// 正在录制的录像
+#[derive(Debug, Default, Resource)]
+pub struct ReplayRecorder(pub Replay);
+
+// 回放状态，GameMode::Replay时按录像自动蓄力起跳
+#[derive(Debug, Resource)]
+pub struct ReplayPlayback {
+    // 要回放的录像，来自上一局或命令行指定的文件
+    pub replay: Option<Replay>,
+    // 下一跳在录像中的序号
+    pub next_jump: usize,
+    // 当前速度在REPLAY_SPEEDS中的序号
+    pub speed: usize,
+}
+impl Default for ReplayPlayback {
+    fn default() -> Self {
+        Self {
+            replay: None,
+            next_jump: 0,
+            speed: NORMAL_SPEED,
+        }
+    }
+}
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay: Some(replay),
+            ..default()
+        }
+    }
+}
+
+// 回放时的速度提示
+#[derive(Debug, Component)]
+pub struct ReplayHud;
+
+pub fn start_recording(
+    game_seed: Res<GameSeed>,
+    mut recorder: ResMut<ReplayRecorder>,
+    mut playback: ResMut<ReplayPlayback>,
+) {
+    recorder.0 = Replay::new(game_seed.current);
+    playback.next_jump = 0;
+}
+
+pub fn record_jumps(mut events: EventReader<ChargeReleased>, mut recorder: ResMut<ReplayRecorder>) {
+    for event in events.read() {
+        recorder.0.charges.push(event.charge.as_nanos() as u64);
+        recorder.0.releases.push(event.next_elapsed);
+        recorder.0.waits.push(event.wait);
+        recorder.0.targets.push(event.target as u32);
+    }
+}
+
+// 游戏结束时保存录像，刷新最高分时另存一份，练习模式的录像只能在本次观看
+pub fn save_replay(
+    game_mode: Res<GameMode>,
+    recorder: Res<ReplayRecorder>,
+    run_stats: Res<RunStats>,
+    save_data: Res<SaveData>,
+    mut playback: ResMut<ReplayPlayback>,
+) {
+    if matches!(*game_mode, GameMode::Replay | GameMode::Autoplay) {
+        return;
+    }
+    playback.replay = Some(recorder.0.clone());
+    if *game_mode == GameMode::Practice {
+        return;
+    }
+    let mut names = vec!["last"];
+    if run_stats.new_record {
+        names.push("best");
+    }
+    for name in names {
+        let Some(path) = Replay::path(&save_data, name) else {
+            continue;
+        };
+        if let Err(err) = recorder.0.store(&path) {
+            warn!("Failed to write replay {}: {err}", path.display());
+        }
+    }
+}
+
+// 按录像中的停留和蓄力时长按下和松开蓄力键
+// 按下时停留时长、松开时蓄力时长、瞄准的平台和移动平台的位置都与录制时完全一致
+#[allow(clippy::too_many_arguments)]
+pub fn drive_replay(
+    mut commands: Commands,
+    mut actions: ResMut<ButtonInput<GameAction>>,
+    mut accumulator: ResMut<Accumulator>,
+    mut playback: ResMut<ReplayPlayback>,
+    prepare_jump_timer: Res<PrepareJumpTimer>,
+    time: Res<Time>,
+    q_player: Query<(&JumpState, &FallState), With<Player>>,
+    mut q_dwell: Query<&mut Dwell, With<CurrentPlatform>>,
+    q_candidates: Query<(Entity, &Candidate)>,
+    mut q_moving_platform: Query<&mut MovingPlatform, With<Candidate>>,
+) {
+    let Ok((jump_state, fall_state)) = q_player.get_single() else {
+        return;
+    };
+    let Some((charge, release, wait, target)) = playback.replay.as_ref().and_then(|replay| {
+        let index = playback.next_jump;
+        let charge = Duration::from_nanos(*replay.charges.get(index)?);
+        Some((
+            charge,
+            *replay.releases.get(index)?,
+            *replay.waits.get(index)?,
+            *replay.targets.get(index)?,
+        ))
+    }) else {
+        return;
+    };
+    match accumulator.0 {
+        None => {
+            if !prepare_jump_timer.0.finished() || !jump_state.completed || !fall_state.completed {
+                return;
+            }
+            let Ok(mut dwell) = q_dwell.get_single_mut() else {
+                return;
+            };
+            // 本帧停留时长将达到录制值时按下，并修正为录制值
+            if dwell.elapsed + time.delta_secs() >= wait {
+                dwell.elapsed = wait;
+                actions.press(GameAction::Charge);
+            }
+        }
+        Some(elapsed) if elapsed >= charge => {
+            accumulator.0 = Some(charge);
+            // 蓄力期间候选平台不会变化，松开时再瞄准
+            aim_candidate(&mut commands, &q_candidates, target as usize);
+            for mut moving in &mut q_moving_platform {
+                moving.elapsed = release;
+            }
+            actions.release(GameAction::Charge);
+            playback.next_jump += 1;
+        }
+        Some(_) => {}
+    }
+}
+
+// 回放时用菜单左右键调整速度
+pub fn change_replay_speed(
+    actions: Res<ButtonInput<GameAction>>,
+    mut playback: ResMut<ReplayPlayback>,
+    mut time: ResMut<Time<Virtual>>,
+    mut q_hud: Query<&mut Text, With<ReplayHud>>,
+) {
+    if actions.just_pressed(GameAction::MenuNext) {
+        playback.speed = (playback.speed + 1).min(REPLAY_SPEEDS.len() - 1);
+    } else if actions.just_pressed(GameAction::MenuPrev) {
+        playback.speed = playback.speed.saturating_sub(1);
+    } else {
+        return;
+    }
+    time.set_relative_speed(REPLAY_SPEEDS[playback.speed]);
+    for mut text in &mut q_hud {
+        text.0 = hud_text(playback.speed);
+    }
+}
+
+pub fn reset_replay_speed(mut playback: ResMut<ReplayPlayback>, mut time: ResMut<Time<Virtual>>) {
+    playback.speed = NORMAL_SPEED;
+    time.set_relative_speed(1.0);
+}
+
+fn hud_text(speed: usize) -> String {
+    format!("Replay x{}", REPLAY_SPEEDS[speed])
+}
+
+pub fn setup_replay_hud(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_mode: Res<GameMode>,
+    playback: Res<ReplayPlayback>,
+) {
+    if *game_mode != GameMode::Replay {
+        return;
+    }
+    commands.spawn((
+        Text::new(hud_text(playback.speed)),
+        TextColor(Color::srgb(0.5, 0.5, 1.0)),
+        TextFont {
+            font: asset_server.load("fonts/num.ttf"),
+            font_size: 24.0,
+            ..default()
+        },
+        Node {
+            position_type: PositionType::Absolute,
+            top: Val::Px(5.0),
+            right: Val::Px(15.0),
+            ..default()
+        },
+        ReplayHud,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_round_trips_through_ron() {
+        let replay = Replay {
+            charges: vec![812_345_678, 1_000_000_001],
+            releases: vec![0.0, 1.234_567_9],
+            waits: vec![2.5, 0.0],
+            targets: vec![0, 2],
+            ..Replay::new(42)
+        };
+        let content = ron::to_string(&replay).unwrap();
+        assert_eq!(ron::from_str::<Replay>(&content).unwrap(), replay);
+    }
+}
@@ -0,0 +1,205 @@
+use bevy::prelude::*;
+use bevy::utils::SystemTime;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+use crate::daily::{DailyChallenge, DailyRecord};
+use crate::platform::GameSeed;
+use crate::ui::{GameMode, Score};
+
+// 保留最近多少局的记录
+pub const MAX_RUN_HISTORY: usize = 20;
+
+// 一局游戏的记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunRecord {
+    pub score: u32,
+    pub seed: u64,
+    // 结束时间，自1970-01-01起的秒数(UTC)
+    pub timestamp: u64,
+    // 游戏时长，秒
+    pub duration: f32,
+}
+
+// 本地存档
+#[derive(Debug, Default, Serialize, Deserialize, Resource)]
+#[serde(default)]
+pub struct SaveData {
+    pub best_score: u32,
+    pub total_jumps: u64,
+    // 最近的游戏记录，最新的在前
+    pub runs: VecDeque<RunRecord>,
+    // 每日挑战记录，键为自1970-01-01起的天数
+    pub daily: BTreeMap<u32, DailyRecord>,
+    // 存档文件路径，为空时只保存在内存中
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+impl SaveData {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn default_path() -> Option<PathBuf> {
+        Some(dirs::data_dir()?.join("jump-jump").join("save.ron"))
+    }
+
+    // 读取存档，不存在或损坏时使用空存档
+    pub fn load() -> Self {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = Self::default_path() {
+            let save_data = match std::fs::read_to_string(&path) {
+                Ok(content) => match ron::from_str(&content) {
+                    Ok(save_data) => save_data,
+                    Err(err) => {
+                        warn!("Failed to parse save file {}: {err}", path.display());
+                        Self::default()
+                    }
+                },
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Self::default(),
+                Err(err) => {
+                    warn!("Failed to read save file {}: {err}", path.display());
+                    Self::default()
+                }
+            };
+            return Self {
+                path: Some(path),
+                ..save_data
+            };
+        }
+        Self::default()
+    }
+
+    // 写入存档，WASM下不保存
+    pub fn store(&self) {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = &self.path {
+            let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .map_err(std::io::Error::other)
+                .and_then(|content| {
+                    if let Some(dir) = path.parent() {
+                        std::fs::create_dir_all(dir)?;
+                    }
+                    std::fs::write(path, content)
+                });
+            if let Err(err) = result {
+                warn!("Failed to write save file {}: {err}", path.display());
+            }
+        }
+    }
+
+    // 记录一局游戏，返回是否刷新最高分
+    pub fn record_run(&mut self, run: RunRecord, jumps: u32) -> bool {
+        let new_record = run.score > self.best_score;
+        self.best_score = self.best_score.max(run.score);
+        self.total_jumps += jumps as u64;
+        self.runs.push_front(run);
+        self.runs.truncate(MAX_RUN_HISTORY);
+        new_record
+    }
+}
+
+// 本局统计
+#[derive(Debug, Default, Resource)]
+pub struct RunStats {
+    pub jumps: u32,
+    // 开局时刻，取自Time<Real>
+    pub started_at: Duration,
+    // 本局是否刷新最高分
+    pub new_record: bool,
+}
+
+pub fn reset_run_stats(mut run_stats: ResMut<RunStats>, time: Res<Time<Real>>) {
+    *run_stats = RunStats {
+        started_at: time.elapsed(),
+        ..default()
+    };
+}
+
+// 游戏结束时记录本局并写入存档，每日挑战的练习局不计分，回放、自动演示和练习模式不记录
+pub fn record_run(
+    score: Res<Score>,
+    game_seed: Res<GameSeed>,
+    game_mode: Res<GameMode>,
+    daily_challenge: Res<DailyChallenge>,
+    time: Res<Time<Real>>,
+    mut run_stats: ResMut<RunStats>,
+    mut save_data: ResMut<SaveData>,
+) {
+    if matches!(
+        *game_mode,
+        GameMode::Replay | GameMode::Autoplay | GameMode::Practice
+    ) {
+        run_stats.new_record = false;
+        return;
+    }
+    let scored = *game_mode != GameMode::Daily || daily_challenge.scored;
+    if scored {
+        let run = RunRecord {
+            score: score.0,
+            seed: game_seed.current,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+            duration: (time.elapsed() - run_stats.started_at).as_secs_f32(),
+        };
+        run_stats.new_record = save_data.record_run(run, run_stats.jumps);
+    } else {
+        run_stats.new_record = false;
+        save_data.total_jumps += run_stats.jumps as u64;
+    }
+    save_data.store();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(score: u32) -> RunRecord {
+        RunRecord {
+            score,
+            seed: 7,
+            timestamp: 0,
+            duration: 12.5,
+        }
+    }
+
+    #[test]
+    fn record_run_tracks_best_and_history() {
+        let mut save_data = SaveData::default();
+        assert!(save_data.record_run(run(5), 6));
+        assert!(!save_data.record_run(run(3), 4));
+        assert!(!save_data.record_run(run(5), 5));
+        assert!(save_data.record_run(run(8), 9));
+        assert_eq!(save_data.best_score, 8);
+        assert_eq!(save_data.total_jumps, 24);
+        assert_eq!(save_data.runs.front().unwrap().score, 8);
+
+        for i in 0..MAX_RUN_HISTORY as u32 {
+            save_data.record_run(run(i), 1);
+        }
+        assert_eq!(save_data.runs.len(), MAX_RUN_HISTORY);
+    }
+
+    #[test]
+    fn save_data_round_trips_through_ron() {
+        let mut save_data = SaveData::default();
+        save_data.record_run(run(11), 12);
+        save_data.daily.insert(
+            20744,
+            DailyRecord {
+                best: 11,
+                attempted: true,
+            },
+        );
+        let content = ron::to_string(&save_data).unwrap();
+        let loaded: SaveData = ron::from_str(&content).unwrap();
+        assert_eq!(loaded.best_score, 11);
+        assert_eq!(loaded.runs.len(), 1);
+        assert!(loaded.daily[&20744].attempted);
+        // 旧存档缺少的字段使用默认值
+        let partial: SaveData = ron::from_str("(best_score: 3)").unwrap();
+        assert_eq!(partial.best_score, 3);
+        assert!(partial.runs.is_empty());
+    }
+}
@@ -0,0 +1,665 @@
+use bevy::math::{Vec2, Vec3, Vec3Swizzles};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::f32::consts::FRAC_1_SQRT_2;
+use std::path::Path;
+
+use crate::config::GameConfig;
+use crate::difficulty::DifficultyCurve;
+use crate::platform::{
+    rand_platform_color, rand_platform_shape, PlatformLimits, PlatformMotion, PlatformShape,
+    BONUS_DWELL_SECS, PERFECT_LANDING_DISTANCE, PLATFORM_HEIGHT, PLATFORM_SIZE,
+};
+use crate::player::{JumpPhysics, PLAYER_HALF_HEIGHT, PLAYER_RADIUS};
+use crate::registry::{Footprint, PlatformRegistry};
+
+// 不依赖渲染的跳跃规则，Bevy系统和无界面模拟共用
+
+// 赛道上的一个平台
+#[derive(Debug, Clone, PartialEq)]
+pub struct CoursePlatform {
+    // 平台几何中心，移动平台为运动中心
+    pub pos: Vec3,
+    pub shape: PlatformShape,
+    pub motion: Option<PlatformMotion>,
+    // 落到该平台的额外加分，只有分支平台才有
+    pub reward: u32,
+}
+impl CoursePlatform {
+    // 平台底面贴地
+    pub fn new(shape: PlatformShape, pos: Vec2) -> Self {
+        Self {
+            pos: Vec3::new(pos.x, shape.height / 2.0, pos.y),
+            shape,
+            motion: None,
+            reward: 0,
+        }
+    }
+    // 移动elapsed秒后的位置
+    pub fn pos_at(&self, elapsed: f32) -> Vec3 {
+        self.motion
+            .map_or(self.pos, |motion| self.pos + motion.offset(elapsed))
+    }
+    // 站在平台上时角色的高度
+    pub fn stand_y(&self) -> f32 {
+        self.shape.top_y(self.pos) + PLAYER_HALF_HEIGHT
+    }
+    // 平台可能覆盖的范围半径，移动平台包括往复的距离
+    fn reach(&self) -> f32 {
+        self.shape.footprint.radius() * self.shape.size
+            + self.motion.map_or(0.0, |motion| motion.amplitude)
+    }
+    // 两个平台中心至少相距多远才容得下角色
+    fn clearance(&self, other: &Self) -> f32 {
+        self.reach() + other.reach() + 2.0 * PLAYER_RADIUS
+    }
+    // 两个平台之间容不下角色
+    fn crowds(&self, other: &Self) -> bool {
+        self.pos.xz().distance(other.pos.xz()) < self.clearance(other)
+    }
+}
+
+// 第一个平台，内置的标准方形或圆柱平台位于原点
+pub fn first_platform(rng: &mut ChaCha8Rng) -> CoursePlatform {
+    let shape = if rng.gen_bool(0.5) {
+        PlatformShape::new("box", Footprint::Square, PLATFORM_SIZE, PLATFORM_HEIGHT)
+    } else {
+        PlatformShape::new(
+            "cylinder",
+            Footprint::Circle,
+            PLATFORM_SIZE,
+            PLATFORM_HEIGHT,
+        )
+    };
+    CoursePlatform::new(shape, Vec2::ZERO)
+}
+
+// 赛道的前进方向，位于+x与-z之间，与相机视角一致
+pub const FORWARD: Vec2 = Vec2::new(FRAC_1_SQRT_2, -FRAC_1_SQRT_2);
+
+// 从from指向to的水平单位向量，两点重合时沿FORWARD
+pub fn jump_direction(from: Vec3, to: Vec3) -> Vec3 {
+    let direction = (to - from).xz().try_normalize().unwrap_or(FORWARD);
+    Vec3::new(direction.x, 0.0, direction.y)
+}
+
+// 在前进方向两侧direction_spread度以内随机生成下一个平台，可能是沿垂直于跳跃方向往复的移动平台
+pub fn next_platform(
+    rng: &mut ChaCha8Rng,
+    limits: &PlatformLimits,
+    registry: &PlatformRegistry,
+    score: u32,
+    current: &CoursePlatform,
+) -> CoursePlatform {
+    let current_pos = current.pos.xz();
+    let rand_distance = rng.gen_range(limits.min_gap..=limits.max_gap);
+    let spread = limits.direction_spread.to_radians();
+    let direction = Vec2::from_angle(rng.gen_range(-spread..=spread)).rotate(FORWARD);
+    // 平面坐标(x, z)和移动方向
+    let next_pos = current_pos + direction * rand_distance;
+    let axis = Vec3::new(-direction.y, 0.0, direction.x);
+    let mut next = CoursePlatform::new(rand_platform_shape(rng, limits, registry, score), next_pos);
+    let can_move = registry
+        .get(&next.shape.kind)
+        .is_none_or(|platform_type| platform_type.moving);
+    // 不会出现移动平台时不消耗随机数，保证已有种子的赛道不变
+    if can_move && limits.moving_chance > 0.0 && rng.gen_bool(limits.moving_chance.min(1.0) as f64)
+    {
+        next.motion = Some(PlatformMotion {
+            axis,
+            amplitude: limits.moving_amplitude,
+            period: limits.moving_period,
+        });
+    }
+    // 斜向的间距可能小于平台尺寸，此时沿跳跃方向推远，保证与当前平台不重叠
+    let distance = rand_distance.max(current.clearance(&next));
+    if distance > rand_distance {
+        let pos = current_pos + direction * distance;
+        next.pos = Vec3::new(pos.x, next.pos.y, pos.y);
+    }
+    next
+}
+
+// 分支平台的尺寸相对常规平台的比例
+pub const BRANCH_SIZE_SCALE: f32 = 0.7;
+// 分支平台的距离最远可达max_gap的倍数
+pub const BRANCH_GAP_SCALE: f32 = 1.25;
+
+// 下一跳的候选平台，第一个为常规平台
+// 按branch_chance的概率再给出一到两个更远更小的静止分支平台，落上去按距离与尺寸之比额外加分
+// 与其他候选平台挤在一起的分支平台会被舍弃，落到任意候选平台后其余的都会移除，因此每条分支都能继续往下跳
+pub fn next_candidates(
+    rng: &mut ChaCha8Rng,
+    limits: &PlatformLimits,
+    registry: &PlatformRegistry,
+    score: u32,
+    current: &CoursePlatform,
+) -> Vec<CoursePlatform> {
+    let mut candidates = vec![next_platform(rng, limits, registry, score, current)];
+    // 不会出现分支时不消耗随机数，保证已有种子的赛道不变
+    if limits.branch_chance <= 0.0 || !rng.gen_bool(limits.branch_chance.min(1.0) as f64) {
+        return candidates;
+    }
+    let spread = limits.direction_spread.to_radians();
+    for _ in 0..rng.gen_range(1..=2) {
+        let gap = rng.gen_range(limits.max_gap..=limits.max_gap * BRANCH_GAP_SCALE);
+        let direction = Vec2::from_angle(rng.gen_range(-spread..=spread)).rotate(FORWARD);
+        let mut shape = rand_platform_shape(rng, limits, registry, score);
+        shape.size *= BRANCH_SIZE_SCALE;
+        let mut branch = CoursePlatform::new(shape, current.pos.xz() + direction * gap);
+        branch.reward = (gap / branch.shape.size).round() as u32;
+        if !branch.crowds(current) && !candidates.iter().any(|other| branch.crowds(other)) {
+            candidates.push(branch);
+        }
+    }
+    candidates
+}
+
+// 一跳的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JumpOutcome {
+    // 蓄力极短，仍落在当前平台上
+    Stayed,
+    // 落到下一平台
+    Landed { perfect: bool },
+    // 摔落，接触到平台边缘时向tilt方向(水平单位向量)倾倒，否则直接下落
+    Fell { tilt: Option<Vec3> },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Jump {
+    // 落点，落在平台上或倾倒时为站立高度
+    pub landing_pos: Vec3,
+    pub outcome: JumpOutcome,
+    // 落到的候选平台序号
+    pub candidate: Option<usize>,
+}
+
+// 由蓄力时长计算落点和结果，next_elapsed为落地时刻候选平台已移动的时长
+// 起跳时以next_elapsed为0，即候选平台位于运动中心，确定瞄准的落点高度
+pub fn resolve_jump(
+    physics: &JumpPhysics,
+    player_pos: Vec3,
+    current: &CoursePlatform,
+    candidates: &[CoursePlatform],
+    target: usize,
+    charge_secs: f32,
+    next_elapsed: f32,
+) -> Jump {
+    let next_pos = |index: usize| candidates[index].pos_at(next_elapsed);
+    let distance = physics.jump_distance(charge_secs);
+    // 沿角色指向瞄准平台运动中心的方向起跳，高度稍后由落点处的平台决定
+    let mut landing_pos =
+        player_pos + jump_direction(player_pos, candidates[target].pos) * distance;
+    // 候选平台互不重叠，落在哪个上面都算
+    let landed = (0..candidates.len()).find(|&index| {
+        candidates[index]
+            .shape
+            .is_landed_on_platform(next_pos(index), landing_pos)
+    });
+    let touched = (0..candidates.len()).find(|&index| {
+        candidates[index]
+            .shape
+            .is_touched_player(next_pos(index), landing_pos, PLAYER_RADIUS)
+    });
+
+    let outcome = if current
+        .shape
+        .is_landed_on_platform(current.pos, landing_pos)
+    {
+        landing_pos.y = current.stand_y();
+        JumpOutcome::Stayed
+    } else if let Some(index) = landed {
+        let next = &candidates[index];
+        landing_pos.y = next.stand_y();
+        let perfect =
+            next.shape.center_distance(next_pos(index), landing_pos) < PERFECT_LANDING_DISTANCE;
+        JumpOutcome::Landed { perfect }
+    } else if current
+        .shape
+        .is_touched_player(current.pos, landing_pos, PLAYER_RADIUS)
+    {
+        landing_pos.y = current.stand_y();
+        // 从平台中心向外倾倒
+        JumpOutcome::Fell {
+            tilt: Some(jump_direction(current.pos, landing_pos)),
+        }
+    } else if let Some(index) = touched {
+        landing_pos.y = candidates[index].stand_y();
+        JumpOutcome::Fell {
+            tilt: Some(jump_direction(next_pos(index), landing_pos)),
+        }
+    } else {
+        JumpOutcome::Fell { tilt: None }
+    };
+    Jump {
+        landing_pos,
+        outcome,
+        candidate: landed.filter(|_| matches!(outcome, JumpOutcome::Landed { .. })),
+    }
+}
+
+// 恰好落到下一平台中心所需的蓄力时长
+pub fn ideal_charge(physics: &JumpPhysics, player_pos: Vec3, next: Vec3) -> f32 {
+    (next - player_pos).xz().length() / physics.distance_per_charge
+}
+
+// 落到下一平台的得分，落在中心附近额外加分，连续命中中心时加分递增
+pub fn landing_points(combo: &mut u32, perfect: bool) -> u32 {
+    if perfect {
+        *combo += 1;
+        2 * *combo
+    } else {
+        *combo = 0;
+        1
+    }
+}
+
+// 决定赛道和跳跃结果的规则，游戏中与加载的配置资源一致
+#[derive(Debug, Clone, Default)]
+pub struct Rules {
+    pub physics: JumpPhysics,
+    // 未设置时始终使用默认平台参数
+    pub curve: Option<DifficultyCurve>,
+    pub platforms: PlatformRegistry,
+}
+
+// 不启动游戏时直接从assets目录读取规则，文件缺失或无效时使用默认值
+pub fn read_rules(assets_dir: &Path, config_path: &str) -> Rules {
+    let physics = std::fs::read(assets_dir.join(config_path))
+        .ok()
+        .and_then(|bytes| GameConfig::from_ron(&bytes).ok())
+        .map(|config| config.jump)
+        .unwrap_or_default();
+    let curve = std::fs::read(assets_dir.join("config/default.difficulty.ron"))
+        .ok()
+        .and_then(|bytes| DifficultyCurve::from_ron(&bytes).ok());
+    let platforms = std::fs::read(assets_dir.join("config/default.platforms.ron"))
+        .ok()
+        .and_then(|bytes| PlatformRegistry::from_ron(&bytes).ok())
+        .unwrap_or_default();
+    Rules {
+        physics,
+        curve,
+        platforms,
+    }
+}
+
+// 一局完整的无界面游戏
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    pub seed: u64,
+    pub physics: JumpPhysics,
+    // 未设置时始终使用默认平台参数
+    pub curve: Option<DifficultyCurve>,
+    pub platforms: PlatformRegistry,
+    pub rng: ChaCha8Rng,
+    pub player_pos: Vec3,
+    pub current: CoursePlatform,
+    // 下一跳的候选平台，第一个为常规平台
+    pub candidates: Vec<CoursePlatform>,
+    // 瞄准的候选平台序号，生成新的候选平台时重置为0
+    pub target: usize,
+    pub score: u32,
+    pub combo: u32,
+    pub jumps: u32,
+    pub game_over: bool,
+    // 在当前平台上停留的时长，秒，起跳后清零
+    pub dwell: f32,
+    // 当前平台的奖励已领取
+    pub bonus_awarded: bool,
+}
+impl Simulation {
+    pub fn new(seed: u64, rules: Rules) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        // 与游戏中生成平台颜色时消耗同样的随机数，保证同一种子得到相同赛道
+        let current = first_platform(&mut rng);
+        rand_platform_color(&mut rng);
+        let mut sim = Self {
+            seed,
+            physics: rules.physics,
+            curve: rules.curve,
+            platforms: rules.platforms,
+            rng,
+            player_pos: Vec3::new(current.pos.x, current.stand_y(), current.pos.z),
+            candidates: Vec::new(),
+            target: 0,
+            current,
+            score: 0,
+            combo: 0,
+            jumps: 0,
+            game_over: false,
+            dwell: 0.0,
+            bonus_awarded: false,
+        };
+        sim.candidates = sim.generate_candidates();
+        sim
+    }
+
+    fn limits(&self) -> PlatformLimits {
+        self.curve
+            .as_ref()
+            .and_then(|curve| curve.sample(self.score))
+            .unwrap_or_default()
+    }
+
+    fn generate_candidates(&mut self) -> Vec<CoursePlatform> {
+        let limits = self.limits();
+        let candidates = next_candidates(
+            &mut self.rng,
+            &limits,
+            &self.platforms,
+            self.score,
+            &self.current,
+        );
+        for _ in &candidates {
+            rand_platform_color(&mut self.rng);
+        }
+        candidates
+    }
+
+    // 瞄准的候选平台
+    pub fn next(&self) -> &CoursePlatform {
+        &self.candidates[self.target]
+    }
+
+    // 改为瞄准序号为target的候选平台，序号无效时不变
+    pub fn aim(&mut self, target: usize) {
+        if target < self.candidates.len() {
+            self.target = target;
+        }
+    }
+
+    // 在当前平台上停留secs秒不起跳，在奖励平台上停留足够久时加分，返回本次加分
+    pub fn dwell(&mut self, secs: f32) -> u32 {
+        if self.game_over {
+            return 0;
+        }
+        self.dwell += secs.max(0.0);
+        let Some(points) = self.current.shape.bonus else {
+            return 0;
+        };
+        if self.bonus_awarded || self.dwell < BONUS_DWELL_SECS {
+            return 0;
+        }
+        self.bonus_awarded = true;
+        self.score += points;
+        points
+    }
+
+    // 下一平台出现后立即蓄力，蓄力charge_secs秒后起跳
+    pub fn jump(&mut self, charge_secs: f32) -> Option<Jump> {
+        self.jump_at(charge_secs, charge_secs)
+    }
+
+    // 下一平台出现release_elapsed秒后起跳，以落地时刻下一平台的位置判定结果，游戏结束后不再响应
+    pub fn jump_at(&mut self, charge_secs: f32, release_elapsed: f32) -> Option<Jump> {
+        if self.game_over {
+            return None;
+        }
+        let aim = resolve_jump(
+            &self.physics,
+            self.player_pos,
+            &self.current,
+            &self.candidates,
+            self.target,
+            charge_secs,
+            0.0,
+        );
+        let touchdown = release_elapsed
+            + self
+                .physics
+                .flight_time(charge_secs, aim.landing_pos.y - self.player_pos.y);
+        let jump = resolve_jump(
+            &self.physics,
+            self.player_pos,
+            &self.current,
+            &self.candidates,
+            self.target,
+            charge_secs,
+            touchdown,
+        );
+        self.jumps += 1;
+        self.dwell = 0.0;
+        self.player_pos = jump.landing_pos;
+        match (jump.outcome, jump.candidate) {
+            (JumpOutcome::Landed { perfect }, Some(index)) => {
+                let landed = self.candidates.swap_remove(index);
+                self.score += landing_points(&mut self.combo, perfect) + landed.reward;
+                // 移动平台停在落地时刻的位置
+                self.current = CoursePlatform {
+                    pos: landed.pos_at(touchdown),
+                    motion: None,
+                    ..landed
+                };
+                self.bonus_awarded = false;
+                self.candidates = self.generate_candidates();
+                self.target = 0;
+            }
+            (JumpOutcome::Fell { .. }, _) => self.game_over = true,
+            _ => {}
+        }
+        Some(jump)
+    }
+
+    // 恰好落到下一平台中心所需的蓄力时长
+    pub fn perfect_charge(&self) -> f32 {
+        ideal_charge(&self.physics, self.player_pos, self.next().pos)
+    }
+
+    // 下一平台移动时，蓄力charge_secs秒恰好在落地时刻回到运动中心所需的等待时长
+    pub fn perfect_wait(&self, charge_secs: f32) -> f32 {
+        let next = self.next();
+        let Some(motion) = next.motion else {
+            return 0.0;
+        };
+        let flight = self
+            .physics
+            .flight_time(charge_secs, next.stand_y() - self.player_pos.y);
+        // 每半个周期经过一次运动中心
+        let half_period = motion.period / 2.0;
+        (half_period - (charge_secs + flight).rem_euclid(half_period)) % half_period
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::platform::PlatformMotion;
+    use bevy::prelude::default;
+
+    #[test]
+    fn dwelling_on_bonus_platform_awards_once() {
+        let mut sim = Simulation::new(7, Rules::default());
+        assert_eq!(sim.dwell(5.0), 0);
+        sim.dwell = 0.0;
+        sim.current.shape.bonus = Some(15);
+        assert_eq!(sim.dwell(1.5), 0);
+        assert_eq!(sim.dwell(1.0), 15);
+        assert_eq!(sim.dwell(5.0), 0);
+        assert_eq!(sim.score, 15);
+
+        // 原地起跳后停留时长重新计算，但同一平台不再奖励
+        sim.jump(0.0);
+        assert_eq!(sim.dwell, 0.0);
+        assert_eq!(sim.dwell(5.0), 0);
+    }
+
+    #[test]
+    fn perfect_jumps_build_combo() {
+        let mut sim = Simulation::new(7, Rules::default());
+        for expected in [2, 6, 12] {
+            let jump = sim.jump(sim.perfect_charge()).unwrap();
+            assert_eq!(jump.outcome, JumpOutcome::Landed { perfect: true });
+            assert_eq!(sim.score, expected);
+        }
+        assert_eq!(sim.combo, 3);
+        assert_eq!(sim.jumps, 3);
+    }
+
+    #[test]
+    fn overshooting_ends_the_game() {
+        let mut sim = Simulation::new(7, Rules::default());
+        let jump = sim.jump(sim.perfect_charge() * 3.0).unwrap();
+        assert_eq!(jump.outcome, JumpOutcome::Fell { tilt: None });
+        assert!(sim.game_over);
+        assert_eq!(sim.jump(1.0), None);
+    }
+
+    #[test]
+    fn tiny_charge_stays_on_current_platform() {
+        let mut sim = Simulation::new(7, Rules::default());
+        let jump = sim.jump(0.01).unwrap();
+        assert_eq!(jump.outcome, JumpOutcome::Stayed);
+        assert_eq!(sim.score, 0);
+        assert!(!sim.game_over);
+    }
+
+    // 下一平台改为移动平台，往复周期为完美一跳滞空时长的4倍
+    fn moving_sim() -> (Simulation, f32) {
+        let mut sim = Simulation::new(7, Rules::default());
+        let axis = Vec3::Y.cross(jump_direction(sim.current.pos, sim.candidates[0].pos));
+        let charge = sim.perfect_charge();
+        let flight = sim
+            .physics
+            .flight_time(charge, sim.candidates[0].stand_y() - sim.player_pos.y);
+        sim.candidates[0].motion = Some(PlatformMotion {
+            axis,
+            amplitude: 2.0,
+            period: 4.0 * flight,
+        });
+        (sim, charge)
+    }
+
+    #[test]
+    fn moving_platform_is_judged_at_touchdown() {
+        let (mut sim, charge) = moving_sim();
+        let wait = sim.perfect_wait(charge);
+        let jump = sim.clone().jump_at(charge, wait + charge).unwrap();
+        assert_eq!(jump.outcome, JumpOutcome::Landed { perfect: true });
+
+        // 起跳时平台恰在运动中心，落地时已移到最远处
+        let period = sim.candidates[0].motion.unwrap().period;
+        let jump = sim.jump_at(charge, period).unwrap();
+        assert_eq!(jump.outcome, JumpOutcome::Fell { tilt: None });
+    }
+
+    #[test]
+    fn landed_moving_platform_stops() {
+        let (mut sim, charge) = moving_sim();
+        let next = sim.candidates[0].clone();
+        sim.jump_at(charge, sim.perfect_wait(charge) + charge)
+            .unwrap();
+        assert_eq!(sim.current.motion, None);
+        assert!(sim.current.pos.distance(next.pos) < 1e-3);
+    }
+
+    #[test]
+    fn off_axis_jump_follows_next_platform() {
+        let mut sim = Simulation::new(7, Rules::default());
+        sim.candidates[0].shape = PlatformShape::new("box", Footprint::Square, 1.0, 1.0);
+        let direction = Vec2::from_angle(0.3).rotate(FORWARD);
+        let offset = Vec3::new(direction.x, 0.0, direction.y) * 3.0;
+        sim.candidates[0].pos = (sim.player_pos + offset).with_y(sim.candidates[0].pos.y);
+        let jump = sim.clone().jump(sim.perfect_charge()).unwrap();
+        assert_eq!(jump.outcome, JumpOutcome::Landed { perfect: true });
+
+        // 越过下一平台边缘时向外倾倒
+        let charge = sim.perfect_charge() + 0.6 / sim.physics.distance_per_charge;
+        let jump = sim.jump(charge).unwrap();
+        let JumpOutcome::Fell { tilt: Some(tilt) } = jump.outcome else {
+            panic!("expected tilt fall, got {:?}", jump.outcome);
+        };
+        assert!(tilt.dot(offset.normalize()) > 0.99);
+    }
+
+    // 在常规平台的另一侧远处放一个分支平台
+    fn add_branch(sim: &mut Simulation, reward: u32) -> CoursePlatform {
+        let side = FORWARD.angle_to(jump_direction(sim.player_pos, sim.candidates[0].pos).xz());
+        let direction = Vec2::from_angle(-side.signum() * 1.4).rotate(FORWARD);
+        let shape = PlatformShape::new("box", Footprint::Square, 1.0, 1.0);
+        let branch = CoursePlatform {
+            reward,
+            ..CoursePlatform::new(shape, sim.current.pos.xz() + direction * 5.0)
+        };
+        sim.candidates.push(branch.clone());
+        branch
+    }
+
+    #[test]
+    fn branches_keep_clear_of_other_candidates() {
+        let limits = PlatformLimits {
+            branch_chance: 1.0,
+            moving_chance: 0.5,
+            ..default()
+        };
+        let registry = PlatformRegistry::default();
+        let mut branched = 0;
+        for seed in 0..50 {
+            let mut rng = ChaCha8Rng::seed_from_u64(seed);
+            let current = first_platform(&mut rng);
+            let candidates = next_candidates(&mut rng, &limits, &registry, 0, &current);
+            assert!((1..=3).contains(&candidates.len()));
+            assert_eq!(candidates[0].reward, 0);
+            for (index, branch) in candidates.iter().enumerate().skip(1) {
+                branched += 1;
+                assert!(branch.reward > 0);
+                assert_eq!(branch.motion, None);
+                assert!(!candidates[..index].iter().any(|other| branch.crowds(other)));
+            }
+        }
+        assert!(branched > 0);
+    }
+
+    #[test]
+    fn consecutive_platforms_never_overlap() {
+        let assets = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets");
+        let rules = read_rules(&assets, "config/default.game.ron");
+        for seed in 0..20 {
+            let mut sim = Simulation::new(seed, rules.clone());
+            let mut course = vec![sim.current.clone()];
+            // 每跳都完美落地，分数随之升高，覆盖难度曲线的各个阶段
+            while sim.jumps < 60 {
+                assert!(!sim.next().crowds(&sim.current));
+                let charge = sim.perfect_charge();
+                let release = sim.perfect_wait(charge) + charge;
+                sim.jump_at(charge, release).unwrap();
+                assert!(!sim.game_over, "seed {seed} jump {}", sim.jumps);
+                course.push(sim.current.clone());
+            }
+            // 静止后的平台轮廓的外接圆也互不相交
+            for (i, a) in course.iter().enumerate() {
+                for b in &course[i + 1..] {
+                    let distance = a.pos.xz().distance(b.pos.xz());
+                    assert!(distance >= a.reach() + b.reach(), "seed {seed}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn aiming_at_branch_scores_its_reward() {
+        let mut sim = Simulation::new(7, Rules::default());
+        let branch = add_branch(&mut sim, 5);
+        sim.aim(3);
+        assert_eq!(sim.target, 0);
+        sim.aim(1);
+        let jump = sim.jump(sim.perfect_charge()).unwrap();
+        assert_eq!(jump.outcome, JumpOutcome::Landed { perfect: true });
+        assert_eq!(jump.candidate, Some(1));
+        assert_eq!(sim.score, 7);
+        assert_eq!(sim.current.pos, branch.pos);
+        assert_eq!(sim.target, 0);
+    }
+
+    #[test]
+    fn same_seed_same_course() {
+        let mut a = Simulation::new(42, Rules::default());
+        let mut b = Simulation::new(42, Rules::default());
+        for _ in 0..10 {
+            assert_eq!(a.candidates, b.candidates);
+            a.jump(a.perfect_charge());
+            b.jump(b.perfect_charge());
+        }
+    }
+}
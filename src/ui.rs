@@ -1,39 +1,144 @@
 use bevy::prelude::*;
-use bevy::window::PrimaryWindow;
+use bevy::window::{PrimaryWindow, WindowFocused};
 
-use crate::player::{JumpState, INITIAL_PLAYER_POS};
+use crate::daily::{format_day, DailyChallenge};
+use crate::input::GameAction;
+use crate::platform::GameSeed;
+use crate::player::{JumpState, Player};
+use crate::replay::{save_replay, ReplayPlayback};
+use crate::save::{record_run, RunStats, SaveData};
+
+// 菜单、计分板、飘分效果和暂停
+pub struct UiPlugin;
+
+impl Plugin for UiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MenuFocus>()
+            .add_systems(
+                Update,
+                (click_button, navigate_menu).run_if(
+                    in_state(GameState::MainMenu)
+                        .or(in_state(GameState::Paused))
+                        .or(in_state(GameState::GameOver)),
+                ),
+            )
+            // Main Menu
+            .add_systems(
+                OnEnter(GameState::MainMenu),
+                (setup_main_menu, despawn_scoreboard),
+            )
+            .add_systems(
+                OnExit(GameState::MainMenu),
+                despawn_screen::<OnMainMenuScreen>,
+            )
+            // Playing
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (
+                    despawn_scoreboard,
+                    setup_scoreboard.after(despawn_scoreboard),
+                )
+                    .in_set(NewGameSet),
+            )
+            .add_systems(
+                Update,
+                (
+                    pause_on_focus_lost,
+                    update_scoreboard,
+                    spawn_score_up_effect,
+                    sync_score_up_effect,
+                    shift_score_up_effect,
+                )
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                Update,
+                toggle_pause.run_if(in_state(GameState::Playing).or(in_state(GameState::Paused))),
+            )
+            // Paused
+            .add_systems(OnEnter(GameState::Paused), (setup_pause_menu, pause_time))
+            .add_systems(
+                OnExit(GameState::Paused),
+                (despawn_screen::<OnPauseMenuScreen>, resume_time),
+            )
+            // GameOver
+            .add_systems(
+                OnEnter(GameState::GameOver),
+                setup_game_over_menu.after(record_run).after(save_replay),
+            )
+            .add_systems(
+                OnExit(GameState::GameOver),
+                despawn_screen::<OnGameOverMenuScreen>,
+            );
+    }
+}
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash, Default, States)]
 pub enum GameState {
     #[default]
     MainMenu,
     Playing,
+    // 游戏暂停，保留当前这局
+    Paused,
     GameOver,
 }
 
-#[derive(Debug, Resource)]
-pub struct GameSounds {
-    pub start: Handle<AudioSource>,
-    pub accumulation: Handle<AudioSource>,
-    pub fall: Handle<AudioSource>,
-    pub success: Handle<AudioSource>,
+// 进入Playing时开始新一局的系统，从暂停恢复时跳过
+#[derive(Debug, Clone, PartialEq, Eq, Hash, SystemSet)]
+pub struct NewGameSet;
+
+// 是否正在从暂停恢复
+#[derive(Debug, Default, Resource)]
+pub struct Resuming(pub bool);
+
+// 游戏模式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Resource)]
+pub enum GameMode {
+    #[default]
+    Normal,
+    // 每日挑战
+    Daily,
+    // 回放录像
+    Replay,
+    // 主菜单闲置时由机器人自动游玩的演示
+    Autoplay,
+    // 练习模式，蓄力时显示预计落点，成绩不保存
+    Practice,
 }
 
 #[derive(Component)]
 pub enum MenuButtonAction {
     StartGame,
+    StartDailyChallenge,
+    StartPractice,
     RestartGame,
+    ResumeGame,
+    WatchReplay,
     BackToMainMenu,
 }
 
+// 键盘/手柄导航时菜单默认选中的按钮
+#[derive(Component)]
+pub struct DefaultMenuFocus;
+
+// 键盘/手柄导航时当前选中的菜单按钮
+#[derive(Debug, Default, Resource)]
+pub struct MenuFocus(pub Option<Entity>);
+
 #[derive(Component)]
 pub struct OnMainMenuScreen;
 #[derive(Component)]
 pub struct OnGameOverMenuScreen;
+#[derive(Component)]
+pub struct OnPauseMenuScreen;
 
 #[derive(Debug, Resource)]
 pub struct Score(pub u32);
 
+// 连续完美落地次数
+#[derive(Debug, Resource)]
+pub struct Combo(pub u32);
+
 #[derive(Debug, Component)]
 pub struct Scoreboard;
 
@@ -42,18 +147,17 @@ pub struct ScoreUpQueue(pub Vec<ScoreUpEvent>);
 #[derive(Debug)]
 pub struct ScoreUpEvent {
     pub landing_pos: Vec3,
+    // 本次得分
+    pub points: u32,
+    // 是否完美落地
+    pub perfect: bool,
 }
 
 #[derive(Debug, Component)]
-pub struct ScoreUpEffect(pub Vec3);
-
-pub fn setup_game_sounds(mut commands: Commands, asset_server: Res<AssetServer>) {
-    commands.insert_resource(GameSounds {
-        start: asset_server.load("sounds/start.mp3"),
-        accumulation: asset_server.load("sounds/accumulation.mp3"),
-        fall: asset_server.load("sounds/fall.mp3"),
-        success: asset_server.load("sounds/success.mp3"),
-    });
+pub struct ScoreUpEffect {
+    pub pos: Vec3,
+    // 上升到该高度后消失
+    pub end_y: f32,
 }
 
 pub fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
@@ -77,9 +181,7 @@ pub fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                 },))
                 .with_children(|parent| {
                     // 标题
-                    parent.spawn((ImageNode::new(
-                        asset_server.load("texture/title.png").into(),
-                    ),));
+                    parent.spawn((ImageNode::new(asset_server.load("texture/title.png")),));
 
                     // 开始按钮
                     parent.spawn((
@@ -92,14 +194,78 @@ pub fn setup_main_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
                             align_items: AlignItems::Center,
                             ..default()
                         },
-                        ImageNode::new(asset_server.load("texture/btn_start.png").into()),
+                        ImageNode::new(asset_server.load("texture/btn_start.png")),
                         MenuButtonAction::StartGame,
+                        DefaultMenuFocus,
                     ));
+
+                    // 每日挑战按钮
+                    parent
+                        .spawn((
+                            Button,
+                            Node {
+                                width: Val::Px(150.),
+                                height: Val::Px(60.),
+                                margin: UiRect::all(Val::Px(10.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            BorderRadius::all(Val::Px(30.0)),
+                            BackgroundColor(Color::srgb(1.0, 0.6, 0.4)),
+                            MenuButtonAction::StartDailyChallenge,
+                        ))
+                        .with_child((
+                            Text::new("Daily"),
+                            TextColor(Color::WHITE),
+                            TextFont {
+                                font: asset_server.load("fonts/num.ttf"),
+                                font_size: 32.0,
+                                ..default()
+                            },
+                        ));
+
+                    // 练习模式按钮
+                    parent
+                        .spawn((
+                            Button,
+                            Node {
+                                width: Val::Px(150.),
+                                height: Val::Px(60.),
+                                margin: UiRect::all(Val::Px(10.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            BorderRadius::all(Val::Px(30.0)),
+                            BackgroundColor(Color::srgb(0.4, 0.8, 0.6)),
+                            MenuButtonAction::StartPractice,
+                        ))
+                        .with_child((
+                            Text::new("Practice"),
+                            TextColor(Color::WHITE),
+                            TextFont {
+                                font: asset_server.load("fonts/num.ttf"),
+                                font_size: 32.0,
+                                ..default()
+                            },
+                        ));
                 });
         });
 }
 
-pub fn setup_game_over_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+#[allow(clippy::too_many_arguments)]
+pub fn setup_game_over_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game_seed: Res<GameSeed>,
+    game_mode: Res<GameMode>,
+    daily_challenge: Res<DailyChallenge>,
+    score: Res<Score>,
+    save_data: Res<SaveData>,
+    run_stats: Res<RunStats>,
+    playback: Res<ReplayPlayback>,
+) {
     commands
         .spawn((
             Node {
@@ -122,6 +288,228 @@ pub fn setup_game_over_menu(mut commands: Commands, asset_server: Res<AssetServe
                     // 标题
                     parent.spawn((ImageNode::new(asset_server.load("texture/title.png")),));
 
+                    // 刷新最高分
+                    if run_stats.new_record {
+                        parent.spawn((
+                            Text::new("New record!"),
+                            TextColor(Color::srgb(1.0, 0.8, 0.2)),
+                            TextFont {
+                                font: asset_server.load("fonts/num.ttf"),
+                                font_size: 48.0,
+                                ..default()
+                            },
+                        ));
+                    }
+
+                    // 本局得分和最高分
+                    parent.spawn((
+                        Text::new(format!(
+                            "Score: {}  Best: {}",
+                            score.0, save_data.best_score
+                        )),
+                        TextColor(Color::srgb(1.0, 0.5, 0.5)),
+                        TextFont {
+                            font: asset_server.load("fonts/num.ttf"),
+                            font_size: 40.0,
+                            ..default()
+                        },
+                    ));
+
+                    // 每日挑战的当日最高分
+                    if *game_mode == GameMode::Daily {
+                        let record = save_data
+                            .daily
+                            .get(&daily_challenge.day)
+                            .copied()
+                            .unwrap_or_default();
+                        let text = if daily_challenge.scored {
+                            format!(
+                                "Daily {}  Best: {}",
+                                format_day(daily_challenge.day),
+                                record.best
+                            )
+                        } else {
+                            format!(
+                                "Daily {}  Best: {}  (practice)",
+                                format_day(daily_challenge.day),
+                                record.best
+                            )
+                        };
+                        parent.spawn((
+                            Text::new(text),
+                            TextColor(Color::srgb(1.0, 0.6, 0.4)),
+                            TextFont {
+                                font: asset_server.load("fonts/num.ttf"),
+                                font_size: 24.0,
+                                ..default()
+                            },
+                        ));
+                    }
+
+                    if *game_mode == GameMode::Practice {
+                        parent.spawn((
+                            Text::new("Practice"),
+                            TextColor(Color::srgb(0.4, 0.8, 0.6)),
+                            TextFont {
+                                font: asset_server.load("fonts/num.ttf"),
+                                font_size: 24.0,
+                                ..default()
+                            },
+                        ));
+                    }
+
+                    if *game_mode == GameMode::Replay {
+                        parent.spawn((
+                            Text::new("Replay"),
+                            TextColor(Color::srgb(0.5, 0.5, 1.0)),
+                            TextFont {
+                                font: asset_server.load("fonts/num.ttf"),
+                                font_size: 24.0,
+                                ..default()
+                            },
+                        ));
+                    }
+
+                    // 本局种子，可用于重玩相同的平台序列
+                    parent.spawn((
+                        Text::new(format!("Seed: {}", game_seed.current)),
+                        TextColor(Color::srgb(0.5, 0.5, 1.0)),
+                        TextFont {
+                            font: asset_server.load("fonts/num.ttf"),
+                            font_size: 24.0,
+                            ..default()
+                        },
+                    ));
+
+                    parent
+                        .spawn((Node {
+                            flex_direction: FlexDirection::Row,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },))
+                        .with_children(|parent| {
+                            // 返回按钮
+                            parent.spawn((
+                                Button,
+                                Node {
+                                    width: Val::Px(40.),
+                                    height: Val::Px(40.),
+                                    margin: UiRect::all(Val::Px(10.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                ImageNode::new(asset_server.load("texture/btn_home.png")),
+                                MenuButtonAction::BackToMainMenu,
+                            ));
+
+                            // 重新开始按钮
+                            parent.spawn((
+                                Button,
+                                Node {
+                                    width: Val::Px(150.),
+                                    height: Val::Px(60.),
+                                    margin: UiRect::all(Val::Px(10.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                ImageNode::new(asset_server.load("texture/btn_restart.png")),
+                                MenuButtonAction::RestartGame,
+                                DefaultMenuFocus,
+                            ));
+                        });
+
+                    // 观看本局录像
+                    if playback.replay.is_some() {
+                        parent
+                            .spawn((
+                                Button,
+                                Node {
+                                    width: Val::Px(150.),
+                                    height: Val::Px(40.),
+                                    margin: UiRect::all(Val::Px(10.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                BorderRadius::all(Val::Px(20.0)),
+                                BackgroundColor(Color::srgb(0.5, 0.5, 1.0)),
+                                MenuButtonAction::WatchReplay,
+                            ))
+                            .with_child((
+                                Text::new("Replay"),
+                                TextColor(Color::WHITE),
+                                TextFont {
+                                    font: asset_server.load("fonts/num.ttf"),
+                                    font_size: 24.0,
+                                    ..default()
+                                },
+                            ));
+                    }
+                });
+        });
+}
+
+pub fn setup_pause_menu(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            Node {
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            // 半透明遮罩
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.5)),
+            OnPauseMenuScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((Node {
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },))
+                .with_children(|parent| {
+                    parent.spawn((
+                        Text::new("Paused"),
+                        TextColor(Color::WHITE),
+                        TextFont {
+                            font: asset_server.load("fonts/num.ttf"),
+                            font_size: 48.0,
+                            ..default()
+                        },
+                    ));
+
+                    // 继续按钮
+                    parent
+                        .spawn((
+                            Button,
+                            Node {
+                                width: Val::Px(150.),
+                                height: Val::Px(60.),
+                                margin: UiRect::all(Val::Px(10.0)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            BorderRadius::all(Val::Px(30.0)),
+                            BackgroundColor(Color::srgb(0.4, 0.8, 0.5)),
+                            MenuButtonAction::ResumeGame,
+                            DefaultMenuFocus,
+                        ))
+                        .with_child((
+                            Text::new("Resume"),
+                            TextColor(Color::WHITE),
+                            TextFont {
+                                font: asset_server.load("fonts/num.ttf"),
+                                font_size: 32.0,
+                                ..default()
+                            },
+                        ));
+
                     parent
                         .spawn((Node {
                             flex_direction: FlexDirection::Row,
@@ -204,12 +592,17 @@ pub fn sync_score_up_effect(
     q_camera: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
     q_windows: Query<&Window, With<PrimaryWindow>>,
 ) {
+    // 无窗口运行时没有可同步的画面
+    let Ok(window) = q_windows.get_single() else {
+        return;
+    };
     let (camera, camera_global_transform) = q_camera.single();
-    let window = q_windows.single();
     for (mut score_up_effect_style, score_up_effect) in &mut q_score_up_effect {
-        let viewport_pos = camera
-            .world_to_viewport(camera_global_transform, score_up_effect.0)
-            .unwrap();
+        let Ok(viewport_pos) =
+            camera.world_to_viewport(camera_global_transform, score_up_effect.pos)
+        else {
+            continue;
+        };
         score_up_effect_style.top = Val::Px(window.resolution.height() - viewport_pos.y);
         score_up_effect_style.left = Val::Px(viewport_pos.x);
     }
@@ -222,11 +615,11 @@ pub fn shift_score_up_effect(
     time: Res<Time>,
 ) {
     for (entity, mut text_color, mut score_up_effect) in &mut q_score_up_effect {
-        score_up_effect.0.y += 1.0 * time.delta_secs();
+        score_up_effect.pos.y += 1.0 * time.delta_secs();
         // 边移动边增加透明度
         let alpha = text_color.0.alpha();
         text_color.0.set_alpha(alpha * 0.97);
-        if score_up_effect.0.y > INITIAL_PLAYER_POS.y + 1.2 {
+        if score_up_effect.pos.y > score_up_effect.end_y {
             commands.entity(entity).despawn();
         }
     }
@@ -237,22 +630,36 @@ pub fn spawn_score_up_effect(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut score_up_queue: ResMut<ScoreUpQueue>,
-    jump_state: Res<JumpState>,
+    q_player: Query<&JumpState, With<Player>>,
     q_camera: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
     q_windows: Query<&Window, With<PrimaryWindow>>,
 ) {
-    if jump_state.completed {
-        let window = q_windows.single();
+    if q_player
+        .get_single()
+        .is_ok_and(|jump_state| jump_state.completed)
+    {
+        // 无窗口运行时直接丢弃飘分
+        let Ok(window) = q_windows.get_single() else {
+            score_up_queue.0.clear();
+            return;
+        };
         // 启动score up动画
         for score_up_event in score_up_queue.0.iter_mut() {
             let (camera, camera_global_transform) = q_camera.single();
-            let viewport_pos = camera
-                .world_to_viewport(camera_global_transform, score_up_event.landing_pos)
-                .unwrap();
-            dbg!(viewport_pos);
+            let Ok(viewport_pos) =
+                camera.world_to_viewport(camera_global_transform, score_up_event.landing_pos)
+            else {
+                continue;
+            };
+            // 完美落地使用醒目的颜色
+            let color = if score_up_event.perfect {
+                Color::srgb(1.0, 0.8, 0.2)
+            } else {
+                Color::srgb(0.5, 0.5, 1.0)
+            };
             commands.spawn((
-                Text::new("+1"),
-                TextColor(Color::srgb(0.5, 0.5, 1.0)),
+                Text::new(format!("+{}", score_up_event.points)),
+                TextColor(color),
                 TextFont {
                     font: asset_server.load("fonts/num.ttf"),
                     font_size: 40.0,
@@ -264,41 +671,203 @@ pub fn spawn_score_up_effect(
                     left: Val::Px(viewport_pos.x),
                     ..default()
                 },
-                ScoreUpEffect(score_up_event.landing_pos),
+                ScoreUpEffect {
+                    pos: score_up_event.landing_pos,
+                    end_y: score_up_event.landing_pos.y + 0.7,
+                },
             ));
         }
         score_up_queue.0.clear();
     }
 }
 
+fn handle_menu_action(
+    menu_button_action: &MenuButtonAction,
+    next_game_state: &mut NextState<GameState>,
+    game_mode: &mut GameMode,
+    resuming: &mut Resuming,
+) {
+    match menu_button_action {
+        MenuButtonAction::StartGame => {
+            info!("StartGame button clicked");
+            *game_mode = GameMode::Normal;
+            next_game_state.set(GameState::Playing);
+        }
+        MenuButtonAction::StartDailyChallenge => {
+            info!("StartDailyChallenge button clicked");
+            *game_mode = GameMode::Daily;
+            next_game_state.set(GameState::Playing);
+        }
+        MenuButtonAction::StartPractice => {
+            info!("StartPractice button clicked");
+            *game_mode = GameMode::Practice;
+            next_game_state.set(GameState::Playing);
+        }
+        MenuButtonAction::RestartGame => {
+            info!("RestartGame button clicked");
+            // 看完录像后重新开始是新的一局
+            if *game_mode == GameMode::Replay {
+                *game_mode = GameMode::Normal;
+            }
+            next_game_state.set(GameState::Playing);
+        }
+        MenuButtonAction::WatchReplay => {
+            info!("WatchReplay button clicked");
+            *game_mode = GameMode::Replay;
+            next_game_state.set(GameState::Playing);
+        }
+        MenuButtonAction::ResumeGame => {
+            info!("ResumeGame button clicked");
+            resuming.0 = true;
+            next_game_state.set(GameState::Playing);
+        }
+        MenuButtonAction::BackToMainMenu => {
+            info!("BackToMainMenu button clicked");
+            next_game_state.set(GameState::MainMenu);
+        }
+    }
+}
+
+#[allow(clippy::type_complexity)]
 pub fn click_button(
     mut interaction_query: Query<
         (&Interaction, &MenuButtonAction),
         (Changed<Interaction>, With<Button>),
     >,
     mut next_game_state: ResMut<NextState<GameState>>,
+    mut game_mode: ResMut<GameMode>,
+    mut resuming: ResMut<Resuming>,
 ) {
     for (interaction, menu_button_action) in &mut interaction_query {
-        match *interaction {
-            Interaction::Pressed => match menu_button_action {
-                MenuButtonAction::StartGame => {
-                    info!("StartGame button clicked");
-                    next_game_state.set(GameState::Playing);
-                }
-                MenuButtonAction::RestartGame => {
-                    info!("RestartGame button clicked");
-                    next_game_state.set(GameState::Playing);
-                }
-                MenuButtonAction::BackToMainMenu => {
-                    info!("BackToMainMenu button clicked");
-                    next_game_state.set(GameState::MainMenu);
-                }
-            },
-            _ => {}
+        if *interaction == Interaction::Pressed {
+            handle_menu_action(
+                menu_button_action,
+                &mut next_game_state,
+                &mut game_mode,
+                &mut resuming,
+            );
+        }
+    }
+}
+
+// 键盘/手柄切换和确认菜单按钮
+#[allow(clippy::type_complexity)]
+pub fn navigate_menu(
+    mut commands: Commands,
+    actions: Res<ButtonInput<GameAction>>,
+    mut menu_focus: ResMut<MenuFocus>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut game_mode: ResMut<GameMode>,
+    mut resuming: ResMut<Resuming>,
+    q_buttons: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            &MenuButtonAction,
+            Has<DefaultMenuFocus>,
+        ),
+        With<Button>,
+    >,
+) {
+    let step: isize = if actions.just_pressed(GameAction::MenuNext) {
+        1
+    } else if actions.just_pressed(GameAction::MenuPrev) {
+        -1
+    } else {
+        0
+    };
+    let confirm = actions.just_pressed(GameAction::MenuConfirm);
+    if step == 0 && !confirm {
+        return;
+    }
+
+    // 按屏幕位置从上到下、从左到右排列
+    let mut buttons: Vec<_> = q_buttons.iter().collect();
+    if buttons.is_empty() {
+        return;
+    }
+    buttons.sort_by(|a, b| {
+        let (a, b) = (a.1.translation(), b.1.translation());
+        a.y.total_cmp(&b.y).then(a.x.total_cmp(&b.x))
+    });
+
+    let focused = menu_focus
+        .0
+        .and_then(|focus| buttons.iter().position(|button| button.0 == focus));
+    let index = match focused {
+        Some(index) => (index as isize + step).rem_euclid(buttons.len() as isize) as usize,
+        // 尚未选中时先选中默认按钮
+        None => buttons.iter().position(|button| button.3).unwrap_or(0),
+    };
+
+    if confirm {
+        handle_menu_action(
+            buttons[index].2,
+            &mut next_game_state,
+            &mut game_mode,
+            &mut resuming,
+        );
+    }
+    menu_focus.0 = Some(buttons[index].0);
+    for (entity, ..) in &buttons {
+        let color = if *entity == buttons[index].0 {
+            Color::srgb(1.0, 0.8, 0.2)
+        } else {
+            Color::NONE
+        };
+        commands
+            .entity(*entity)
+            .insert(Outline::new(Val::Px(4.0), Val::Px(2.0), color));
+    }
+}
+
+// 按暂停键暂停或继续
+pub fn toggle_pause(
+    actions: Res<ButtonInput<GameAction>>,
+    game_state: Res<State<GameState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+    mut resuming: ResMut<Resuming>,
+) {
+    if !actions.just_pressed(GameAction::Pause) {
+        return;
+    }
+    match game_state.get() {
+        GameState::Playing => next_game_state.set(GameState::Paused),
+        GameState::Paused => {
+            resuming.0 = true;
+            next_game_state.set(GameState::Playing);
         }
+        _ => {}
+    }
+}
+
+// 窗口失去焦点时自动暂停
+pub fn pause_on_focus_lost(
+    mut focus_events: EventReader<WindowFocused>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    if focus_events.read().any(|event| !event.focused) {
+        next_game_state.set(GameState::Paused);
     }
 }
 
+// 暂停时冻结虚拟时间，粒子等依赖虚拟时间的效果随之停止
+pub fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+pub fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+pub fn not_resuming(resuming: Res<Resuming>) -> bool {
+    !resuming.0
+}
+
+pub fn finish_resuming(mut resuming: ResMut<Resuming>) {
+    resuming.0 = false;
+}
+
 pub fn despawn_screen<T: Component>(to_despawn: Query<Entity, With<T>>, mut commands: Commands) {
     for entity in &to_despawn {
         commands.entity(entity).despawn_recursive();
@@ -311,6 +880,7 @@ pub fn despawn_scoreboard(mut commands: Commands, q_scoreboard: Query<Entity, Wi
     }
 }
 
-pub fn reset_score(mut score: ResMut<Score>) {
+pub fn reset_score(mut score: ResMut<Score>, mut combo: ResMut<Combo>) {
     score.0 = 0;
+    combo.0 = 0;
 }
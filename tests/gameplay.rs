@@ -0,0 +1,558 @@
+use std::path::Path;
+use std::time::Duration;
+
+use bevy::app::PluginGroupBuilder;
+use bevy::input::keyboard::{Key, KeyboardInput, NativeKey};
+use bevy::input::ButtonState;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+use jump_jump::audio::{AccumulationSound, AudioPlugin};
+use jump_jump::bot::Autoplay;
+use jump_jump::config::{GameConfig, GameConfigHandle};
+use jump_jump::difficulty::{DifficultyCurve, DifficultyCurveHandle};
+use jump_jump::ghost::{BestReplay, Ghost};
+use jump_jump::headless::HeadlessPlugin;
+use jump_jump::input::GameAction;
+use jump_jump::platform::{
+    Candidate, CurrentPlatform, Dwell, GameSeed, MovingPlatform, NextPlatform, PlatformMotion,
+    PlatformShape, TargetMarker,
+};
+use jump_jump::player::{Accumulator, FallState, FallType, JumpState, Player};
+use jump_jump::practice::{AimReticle, TrajectoryDot, TRAJECTORY_DOTS};
+use jump_jump::registry::{Footprint, PlatformRegistry, PlatformRegistryHandle};
+use jump_jump::replay::ReplayPlayback;
+use jump_jump::save::{RunStats, SaveData};
+use jump_jump::sim::{jump_direction, FORWARD};
+use jump_jump::ui::{Combo, GameMode, GameState, Score};
+use jump_jump::JumpJumpPlugin;
+
+// 每帧固定推进的时长
+const FRAME: Duration = Duration::from_millis(10);
+
+fn test_app() -> App {
+    app_with(JumpJumpPlugin.build())
+}
+
+fn app_with(game: PluginGroupBuilder) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, HeadlessPlugin, game))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(FRAME))
+        .insert_resource(GameSeed::new(Some(42)));
+    app.update();
+    preload_config(&mut app);
+    app
+}
+
+// 直接读取配置文件代替异步加载，测试的每一帧都与资源加载的快慢无关
+fn preload_config(app: &mut App) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("assets/config");
+    let read = |name: &str| std::fs::read(dir.join(name)).unwrap();
+    let config = GameConfig::from_ron(&read("default.game.ron")).unwrap();
+    let curve = DifficultyCurve::from_ron(&read("default.difficulty.ron")).unwrap();
+    let registry = PlatformRegistry::from_ron(&read("default.platforms.ron")).unwrap();
+
+    let world = app.world_mut();
+    let handle = world
+        .resource_mut::<Assets<GameConfig>>()
+        .add(config.clone());
+    world.insert_resource(GameConfigHandle(handle));
+    world.insert_resource(config);
+    let handle = world.resource_mut::<Assets<DifficultyCurve>>().add(curve);
+    world.insert_resource(DifficultyCurveHandle(handle));
+    let handle = world
+        .resource_mut::<Assets<PlatformRegistry>>()
+        .add(registry);
+    world.insert_resource(PlatformRegistryHandle(handle));
+}
+
+fn send_key(app: &mut App, key_code: KeyCode, state: ButtonState) {
+    queue_key(app, key_code, state);
+    app.update();
+}
+
+// 只发送按键事件，下一次update时才处理
+fn queue_key(app: &mut App, key_code: KeyCode, state: ButtonState) {
+    app.world_mut().send_event(KeyboardInput {
+        key_code,
+        logical_key: Key::Unidentified(NativeKey::Unidentified),
+        state,
+        repeat: false,
+        window: Entity::PLACEHOLDER,
+    });
+}
+
+fn run_frames(app: &mut App, frames: u32) {
+    for _ in 0..frames {
+        app.update();
+    }
+}
+
+fn state(app: &App) -> GameState {
+    app.world().resource::<State<GameState>>().get().clone()
+}
+
+// 在主菜单按确认键开始游戏，并等待防误触计时结束
+fn start_game(app: &mut App) {
+    assert_eq!(state(app), GameState::MainMenu);
+    send_key(app, KeyCode::Enter, ButtonState::Pressed);
+    send_key(app, KeyCode::Enter, ButtonState::Released);
+    assert_eq!(state(app), GameState::Playing);
+    run_frames(app, 30);
+    wait_for_next_platform(app);
+}
+
+// 上一跳落地后才会生成下一平台
+fn wait_for_next_platform(app: &mut App) {
+    for _ in 0..100 {
+        let world = app.world_mut();
+        let mut query = world.query_filtered::<(), With<NextPlatform>>();
+        if query.iter(world).next().is_some() {
+            return;
+        }
+        app.update();
+    }
+    panic!("next platform never generated");
+}
+
+fn wait_for_game_over(app: &mut App) {
+    for _ in 0..3000 {
+        if state(app) == GameState::GameOver {
+            return;
+        }
+        app.update();
+    }
+    panic!("game never ended");
+}
+
+// 按住空格蓄力ms毫秒后松开
+fn charge(app: &mut App, ms: u64) {
+    let frames = (ms / FRAME.as_millis() as u64).max(1) as u32;
+    send_key(app, KeyCode::Space, ButtonState::Pressed);
+    run_frames(app, frames - 1);
+    send_key(app, KeyCode::Space, ButtonState::Released);
+}
+
+fn wait_for_landing(app: &mut App) {
+    for _ in 0..500 {
+        if player_state::<JumpState>(app).completed {
+            return;
+        }
+        app.update();
+    }
+    panic!("jump never completed");
+}
+
+// 玩家身上的跳跃或摔落状态
+fn player_state<C: Component>(app: &mut App) -> &C {
+    let world = app.world_mut();
+    world.query_filtered::<&C, With<Player>>().single(world)
+}
+
+fn translation<F: bevy::ecs::query::QueryFilter>(app: &mut App) -> Vec3 {
+    let world = app.world_mut();
+    world
+        .query_filtered::<&Transform, F>()
+        .single(world)
+        .translation
+}
+
+fn next_platform(app: &mut App) -> (Entity, Vec3, PlatformShape) {
+    let world = app.world_mut();
+    let mut query =
+        world.query_filtered::<(Entity, &Transform, &PlatformShape), With<NextPlatform>>();
+    let (entity, transform, shape) = query.single(world);
+    (entity, transform.translation, shape.clone())
+}
+
+// 沿跳跃方向到达距下一平台中心offset处所需的蓄力时长
+fn charge_ms_to_next(app: &mut App, offset: f32) -> u64 {
+    let player = translation::<With<Player>>(app);
+    let (_, next, _) = next_platform(app);
+    let distance = (next - player).xz().length() + offset;
+    let physics = &app.world().resource::<GameConfig>().jump;
+    (distance / physics.distance_per_charge * 1000.0).round() as u64
+}
+
+#[test]
+fn menu_to_playing_to_game_over() {
+    let mut app = test_app();
+    start_game(&mut app);
+    charge(&mut app, 3000);
+    wait_for_game_over(&mut app);
+}
+
+#[test]
+fn tap_within_one_frame_still_jumps() {
+    let mut app = test_app();
+    start_game(&mut app);
+    // 按下和松开在同一帧
+    queue_key(&mut app, KeyCode::Space, ButtonState::Pressed);
+    queue_key(&mut app, KeyCode::Space, ButtonState::Released);
+    app.update();
+    assert_eq!(app.world().resource::<RunStats>().jumps, 1);
+    assert!(app.world().resource::<Accumulator>().0.is_none());
+    assert!(!app
+        .world()
+        .resource::<ButtonInput<GameAction>>()
+        .pressed(GameAction::Charge));
+}
+
+#[test]
+fn restart_after_pausing_mid_charge_does_not_jump() {
+    let mut app = test_app();
+    start_game(&mut app);
+    send_key(&mut app, KeyCode::Space, ButtonState::Pressed);
+    run_frames(&mut app, 20);
+    send_key(&mut app, KeyCode::Escape, ButtonState::Pressed);
+    send_key(&mut app, KeyCode::Escape, ButtonState::Released);
+    assert_eq!(state(&app), GameState::Paused);
+    // 暂停期间松开，蓄力仍保留
+    send_key(&mut app, KeyCode::Space, ButtonState::Released);
+    assert!(app.world().resource::<Accumulator>().0.is_some());
+
+    // 在暂停菜单中重新开始
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    run_frames(&mut app, 60);
+    assert_eq!(state(&app), GameState::Playing);
+    assert!(app.world().resource::<Accumulator>().0.is_none());
+    assert_eq!(app.world().resource::<RunStats>().jumps, 0);
+    assert!(player_state::<JumpState>(&mut app).completed);
+    let world = app.world_mut();
+    let mut q_sound = world.query_filtered::<(), With<AccumulationSound>>();
+    assert_eq!(q_sound.iter(world).count(), 0);
+}
+
+#[test]
+fn replay_reproduces_the_run() {
+    let mut app = test_app();
+    start_game(&mut app);
+    for offset in [0.0, 0.3] {
+        let ms = charge_ms_to_next(&mut app, offset);
+        charge(&mut app, ms);
+        wait_for_landing(&mut app);
+        app.update();
+        wait_for_next_platform(&mut app);
+    }
+    charge(&mut app, 3000);
+    wait_for_game_over(&mut app);
+    let score = app.world().resource::<Score>().0;
+    assert!(score > 0);
+    let replay = app
+        .world()
+        .resource::<ReplayPlayback>()
+        .replay
+        .clone()
+        .unwrap();
+    assert_eq!(replay.seed, 42);
+    assert_eq!(replay.charges.len(), 3);
+
+    // 回放不受当前种子设置影响
+    app.world_mut().resource_mut::<GameSeed>().fixed = Some(7);
+    *app.world_mut().resource_mut::<GameMode>() = GameMode::Replay;
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    app.update();
+    assert_eq!(app.world().resource::<Score>().0, 0);
+    // 回放期间的按键不影响录像
+    run_frames(&mut app, 30);
+    charge(&mut app, 500);
+    send_key(&mut app, KeyCode::ArrowLeft, ButtonState::Pressed);
+    wait_for_game_over(&mut app);
+
+    assert_eq!(app.world().resource::<GameSeed>().current, 42);
+    assert_eq!(app.world().resource::<Score>().0, score);
+    assert_eq!(app.world().resource::<ReplayPlayback>().next_jump, 3);
+
+    // 游戏结束画面默认选中重新开始，开始的是新的一局
+    send_key(&mut app, KeyCode::Enter, ButtonState::Pressed);
+    send_key(&mut app, KeyCode::Enter, ButtonState::Released);
+    assert_eq!(state(&app), GameState::Playing);
+    assert_eq!(*app.world().resource::<GameMode>(), GameMode::Normal);
+}
+
+#[test]
+fn ghost_repeats_best_run() {
+    let mut app = test_app();
+    start_game(&mut app);
+    let ms = charge_ms_to_next(&mut app, 0.0);
+    charge(&mut app, ms);
+    wait_for_landing(&mut app);
+    let best_landing = translation::<With<Player>>(&mut app);
+    app.update();
+    wait_for_next_platform(&mut app);
+    charge(&mut app, 3000);
+    wait_for_game_over(&mut app);
+    assert!(app.world().resource::<BestReplay>().0.is_some());
+
+    // 同一种子再来一局，玩家第一跳就摔落，幽灵仍落在最佳一局的位置
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    app.update();
+    run_frames(&mut app, 30);
+    wait_for_next_platform(&mut app);
+    charge(&mut app, 3000);
+    let world = app.world_mut();
+    let mut q_ghost = world.query_filtered::<(&Transform, &JumpState), With<Ghost>>();
+    assert!(!q_ghost.single(world).1.completed);
+    for _ in 0..500 {
+        app.update();
+    }
+    let world = app.world_mut();
+    let (ghost, jump_state) = q_ghost.single(world);
+    assert!(jump_state.completed);
+    assert!(ghost.translation.distance(best_landing) < 1e-3);
+}
+
+#[test]
+fn autoplay_bot_plays_until_interrupted() {
+    let mut app = test_app();
+    app.insert_resource(Autoplay::new(1.0));
+    *app.world_mut().resource_mut::<GameMode>() = GameMode::Autoplay;
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    run_frames(&mut app, 1200);
+    // 技能满分的机器人每一跳都完美落地，第n次连续完美落地得2n分
+    let combo = app.world().resource::<Combo>().0;
+    assert!(combo >= 3);
+    assert_eq!(app.world().resource::<Score>().0, combo * (combo + 1));
+    assert_eq!(state(&app), GameState::Playing);
+
+    send_key(&mut app, KeyCode::KeyA, ButtonState::Pressed);
+    app.update();
+    assert_eq!(state(&app), GameState::MainMenu);
+    assert_eq!(*app.world().resource::<GameMode>(), GameMode::Normal);
+}
+
+#[test]
+fn bot_times_jump_onto_moving_platform() {
+    let mut app = test_app();
+    app.insert_resource(Autoplay::new(1.0));
+    *app.world_mut().resource_mut::<GameMode>() = GameMode::Autoplay;
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    app.update();
+    wait_for_next_platform(&mut app);
+
+    // 把下一平台改为沿垂直于跳跃方向往复的移动平台
+    let current = translation::<With<CurrentPlatform>>(&mut app);
+    let (next_entity, next, _) = next_platform(&mut app);
+    let axis = Vec3::Y.cross(jump_direction(current, next));
+    app.world_mut()
+        .entity_mut(next_entity)
+        .insert(MovingPlatform {
+            motion: PlatformMotion {
+                axis,
+                amplitude: 0.8,
+                period: 2.0,
+            },
+            origin: next,
+            elapsed: 0.0,
+        });
+    for _ in 0..2000 {
+        if app.world().resource::<Score>().0 > 0 {
+            break;
+        }
+        app.update();
+    }
+    assert_eq!(app.world().resource::<Score>().0, 2);
+    let world = app.world_mut();
+    let mut q_moving = world.query::<&MovingPlatform>();
+    assert!(q_moving.get(world, next_entity).is_err());
+    // 落地后平台停止，角色站在平台中心附近
+    let player = translation::<With<Player>>(&mut app);
+    let landed = translation::<With<CurrentPlatform>>(&mut app);
+    assert!(player.xz().distance(landed.xz()) < 0.2);
+}
+
+#[test]
+fn runs_without_audio_plugin() {
+    let mut app = app_with(JumpJumpPlugin.build().disable::<AudioPlugin>());
+    start_game(&mut app);
+    let ms = charge_ms_to_next(&mut app, 0.0);
+    charge(&mut app, ms);
+    wait_for_landing(&mut app);
+    assert!(app.world().resource::<Score>().0 > 0);
+}
+
+#[test]
+fn landing_scores_and_swaps_platform_markers() {
+    let mut app = test_app();
+    start_game(&mut app);
+    let (next_entity, _, _) = next_platform(&mut app);
+    let ms = charge_ms_to_next(&mut app, 0.0);
+    charge(&mut app, ms);
+    wait_for_landing(&mut app);
+
+    assert_eq!(state(&app), GameState::Playing);
+    assert!(app.world().resource::<Score>().0 > 0);
+    let world = app.world_mut();
+    let mut current = world.query_filtered::<Entity, With<CurrentPlatform>>();
+    assert_eq!(current.single(world), next_entity);
+
+    // 下一帧生成新的下一平台
+    app.update();
+    let (new_next_entity, _, _) = next_platform(&mut app);
+    assert_ne!(new_next_entity, next_entity);
+}
+
+#[test]
+fn dwelling_on_bonus_platform_scores_once() {
+    let mut app = test_app();
+    start_game(&mut app);
+    let world = app.world_mut();
+    let mut query = world.query_filtered::<&mut PlatformShape, With<CurrentPlatform>>();
+    query.single_mut(world).bonus = Some(30);
+
+    run_frames(&mut app, 150);
+    assert_eq!(app.world().resource::<Score>().0, 0);
+    run_frames(&mut app, 100);
+    assert_eq!(app.world().resource::<Score>().0, 30);
+    run_frames(&mut app, 300);
+    assert_eq!(app.world().resource::<Score>().0, 30);
+    let world = app.world_mut();
+    let mut dwell = world.query_filtered::<&Dwell, With<CurrentPlatform>>();
+    assert!(dwell.single(world).awarded);
+}
+
+#[test]
+fn aiming_switches_to_branch_platform() {
+    let mut app = test_app();
+    start_game(&mut app);
+    let player = translation::<With<Player>>(&mut app);
+    let current = translation::<With<CurrentPlatform>>(&mut app);
+    let (primary, next, _) = next_platform(&mut app);
+
+    // 在常规平台另一侧的远处放一个分支平台
+    let right = FORWARD.perp();
+    let primary_side = (next - player).xz().dot(right).signum();
+    let direction = Vec2::from_angle(-primary_side * 1.4).rotate(FORWARD);
+    let branch_pos = current + Vec3::new(direction.x, 0.0, direction.y) * 5.0;
+    let branch = app
+        .world_mut()
+        .spawn((
+            Transform::from_translation(branch_pos),
+            PlatformShape::new("box", Footprint::Square, 1.0, current.y * 2.0),
+            Candidate {
+                index: 1,
+                reward: 5,
+            },
+        ))
+        .id();
+    let key = if primary_side > 0.0 {
+        KeyCode::ArrowLeft
+    } else {
+        KeyCode::ArrowRight
+    };
+    send_key(&mut app, key, ButtonState::Pressed);
+    send_key(&mut app, key, ButtonState::Released);
+    let (aimed, _, _) = next_platform(&mut app);
+    assert_eq!(aimed, branch);
+    let world = app.world_mut();
+    let mut markers = world.query_filtered::<&Parent, With<TargetMarker>>();
+    assert_eq!(markers.single(world).get(), branch);
+
+    let ms = charge_ms_to_next(&mut app, 0.0);
+    charge(&mut app, ms);
+    wait_for_landing(&mut app);
+    // 落地得分加上分支平台的额外加分，未选择的候选平台移除
+    assert!(matches!(app.world().resource::<Score>().0, 6 | 7));
+    let world = app.world_mut();
+    let mut q_current = world.query_filtered::<Entity, With<CurrentPlatform>>();
+    assert_eq!(q_current.single(world), branch);
+    assert!(world.get_entity(primary).is_err());
+}
+
+#[test]
+fn practice_mode_previews_landing_without_saving() {
+    let mut app = test_app();
+    *app.world_mut().resource_mut::<GameMode>() = GameMode::Practice;
+    app.world_mut()
+        .resource_mut::<NextState<GameState>>()
+        .set(GameState::Playing);
+    app.update();
+    run_frames(&mut app, 30);
+    wait_for_next_platform(&mut app);
+
+    // 落点标记位于按已蓄力时长起跳的落点
+    let player = translation::<With<Player>>(&mut app);
+    let (_, next, _) = next_platform(&mut app);
+    let ms = charge_ms_to_next(&mut app, 0.0);
+    send_key(&mut app, KeyCode::Space, ButtonState::Pressed);
+    run_frames(&mut app, (ms / FRAME.as_millis() as u64) as u32 - 1);
+    let charged = app.world().resource::<Accumulator>().0.unwrap();
+    let distance = app
+        .world()
+        .resource::<GameConfig>()
+        .jump
+        .jump_distance(charged.as_secs_f32());
+    let expected = player + jump_direction(player, next) * distance;
+    let world = app.world_mut();
+    let mut q_reticle = world.query_filtered::<(&Transform, &Visibility), With<AimReticle>>();
+    let (reticle, visibility) = q_reticle.single(world);
+    assert_eq!(*visibility, Visibility::Inherited);
+    assert!(reticle.translation.xz().distance(expected.xz()) < 1e-4);
+    let reticle = reticle.translation;
+    let mut q_dots = world.query_filtered::<&Visibility, With<TrajectoryDot>>();
+    assert_eq!(q_dots.iter(world).count(), TRAJECTORY_DOTS);
+
+    // 松开时再蓄力一帧，落点沿跳跃方向稍远
+    send_key(&mut app, KeyCode::Space, ButtonState::Released);
+    wait_for_landing(&mut app);
+    let landing = translation::<With<Player>>(&mut app);
+    let extra = app
+        .world()
+        .resource::<GameConfig>()
+        .jump
+        .jump_distance(FRAME.as_secs_f32());
+    assert!((landing.xz().distance(reticle.xz()) - extra).abs() < 1e-3);
+    assert!(app.world().resource::<Score>().0 > 0);
+
+    // 练习成绩不写入存档
+    charge(&mut app, 3000);
+    wait_for_game_over(&mut app);
+    let save_data = app.world().resource::<SaveData>();
+    assert_eq!(save_data.best_score, 0);
+    assert!(save_data.runs.is_empty());
+}
+
+#[test]
+fn edge_landing_tilts() {
+    let mut app = test_app();
+    start_game(&mut app);
+    let player = translation::<With<Player>>(&mut app);
+    let (_, next, shape) = next_platform(&mut app);
+    // 沿跳跃方向找到平台边缘，落点稍稍越过边缘
+    let direction = jump_direction(player, next);
+    let edge = (0..)
+        .map(|step| step as f32 * 0.01)
+        .find(|&offset| !shape.is_landed_on_platform(next, next + direction * offset))
+        .unwrap();
+    let ms = charge_ms_to_next(&mut app, edge + 0.05);
+    charge(&mut app, ms);
+    // 落地时才判定结果
+    assert!(player_state::<FallState>(&mut app).completed);
+    wait_for_landing(&mut app);
+
+    let fall_state = player_state::<FallState>(&mut app);
+    assert!(!fall_state.completed);
+    assert!(matches!(fall_state.fall_type, FallType::Tilt(_)));
+}
+
+#[test]
+fn far_overshoot_falls_straight() {
+    let mut app = test_app();
+    start_game(&mut app);
+    charge(&mut app, 3000);
+    wait_for_landing(&mut app);
+
+    let fall_state = player_state::<FallState>(&mut app);
+    assert!(!fall_state.completed);
+    assert!(matches!(fall_state.fall_type, FallType::Straight));
+    assert_eq!(app.world().resource::<Score>().0, 0);
+}